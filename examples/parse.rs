@@ -1,5 +1,4 @@
 fn main() {
   let (args,argv) = argmap::parse(std::env::args());
-  eprintln!["args={:?}", &args];
-  eprintln!["argv={:?}", &argv];
+  eprintln!["{}", argmap::debug_format(&args, &argv)];
 }
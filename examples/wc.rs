@@ -4,9 +4,9 @@ type Error = Box<dyn std::error::Error+Send+Sync>;
 type R = Box<dyn io::Read+Unpin>;
 
 fn main() -> Result<(),Error> {
-  let (args,argv) = argmap::new()
+  let (program,args,argv) = argmap::new()
     .booleans(&[ "h", "help", "c", "bytes", "w", "words", "l", "lines" ])
-    .parse(std::env::args());
+    .parse_program(std::env::args());
   if argv.contains_key("h") || argv.contains_key("help") {
     indoc::printdoc![r#"usage: {} {{OPTIONS}} [FILE]
 
@@ -18,7 +18,7 @@ fn main() -> Result<(),Error> {
         -l, --lines   Show number of lines.
         -h, --help    Show this message.
 
-    "#, args.get(0).unwrap_or(&"???".to_string())];
+    "#, program.as_deref().unwrap_or("???")];
     return Ok(());
   }
 
@@ -34,7 +34,7 @@ fn main() -> Result<(),Error> {
   let stdin_file = "-".to_string();
   let infile = argv.get("infile").and_then(|v| v.first()) // --infile=file
     .or_else(|| argv.get("i").and_then(|v| v.first())) // -i file
-    .or_else(|| args.get(1)) // first positional arg after $0
+    .or_else(|| args.first()) // first positional arg
     .unwrap_or(&stdin_file) // default value: "-"
     .as_str();
 
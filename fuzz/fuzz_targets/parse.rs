@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// Split the input on NUL bytes into arguments and parse them with a few configurations.
+fuzz_target!(|data: &[u8]| {
+  let _ = argmap::parse_bytes(data);
+  let args: Vec<String> = data.split(|b| *b == 0)
+    .map(|arg| String::from_utf8_lossy(arg).into_owned())
+    .collect();
+  let _ = argmap::new().strict(true).known(&["a","b"]).try_parse(args.iter());
+  let _ = argmap::new().booleans(&["a"]).kv_separators(&['=','-']).parse_bytes(data);
+  if let Ok(s) = std::str::from_utf8(data) {
+    let _ = argmap::parse_str(s);
+  }
+});
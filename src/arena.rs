@@ -0,0 +1,21 @@
+use crate::{ArgMap,ParseMeta,Run};
+use bumpalo::Bump;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+impl ArgMap {
+  /// Parse like `parse()`, but allocate every string of the result in `arena`, so that the result
+  /// is freed all at once with it. Each argument is copied into the arena once, and keys and values
+  /// borrow from those copies wherever they can, so only strings made by the parser, such as a
+  /// `counted()` total, are allocated again.
+  pub fn parse_in<'a,T>(&self, input: impl Iterator<Item=T>, arena: &'a Bump)
+  -> (Vec<&'a str>,HashMap<&'a str,Vec<&'a str>>)
+  where T: AsRef<str> {
+    let mut input = input.map(|x| Cow::Borrowed(&*arena.alloc_str(x.as_ref())));
+    let tokens = self.run_lenient(&mut input, Run::default(), &mut ParseMeta::default());
+    self.collect_with(tokens, |s| match s {
+      Cow::Borrowed(s) => s,
+      Cow::Owned(s) => arena.alloc_str(&s),
+    })
+  }
+}
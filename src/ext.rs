@@ -0,0 +1,142 @@
+use crate::Map;
+use std::str::FromStr;
+
+/// Read helpers for the `Map` returned by `parse()` that hand back borrowed views instead of
+/// cloned values.
+pub trait ArgMapExt {
+  /// Return all of the values for `key` as a slice, or an empty slice if `key` is missing.
+  ///
+  /// ```
+  /// use argmap::ArgMapExt;
+  /// let (_args,argv) = argmap::parse(["-x","1","-x","2"].iter());
+  /// let xs: Vec<&str> = argv.get_all("x").iter().map(|s| s.as_str()).collect();
+  /// assert_eq![xs, vec!["1","2"]];
+  /// for _y in argv.get_all("y") {
+  ///   unreachable![];
+  /// }
+  /// assert![argv.get_all("y").is_empty()];
+  /// ```
+  fn get_all(&self, key: &str) -> &[String];
+  /// Return the first value for `key` if there is one.
+  fn first(&self, key: &str) -> Option<&str>;
+  /// Return the last value for `key` if there is one.
+  fn last(&self, key: &str) -> Option<&str>;
+  /// Return the last value for `key`, or `default` if there is none.
+  ///
+  /// ```
+  /// use argmap::ArgMapExt;
+  /// let (_args,argv) = argmap::parse(["--infile","a.txt","-c"].iter());
+  /// assert_eq![argv.get_or("infile", "-"), "a.txt"];
+  /// assert_eq![argv.get_or("i", "-"), "-"];
+  /// assert_eq![argv.get_or("c", "-"), "-"];
+  /// ```
+  fn get_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str;
+  /// Return all of the values for `key` as a slice, or `default` if there are none.
+  ///
+  /// ```
+  /// use argmap::ArgMapExt;
+  /// let stdin = vec!["-".to_string()];
+  /// let (_args,argv) = argmap::parse(["-i","a.txt","-i","b.txt","-c"].iter());
+  /// assert_eq![argv.get_all_or("i", &stdin), ["a.txt","b.txt"]];
+  /// assert_eq![argv.get_all_or("infile", &stdin), ["-"]];
+  /// assert_eq![argv.get_all_or("c", &stdin), ["-"]];
+  /// ```
+  fn get_all_or<'a>(&'a self, key: &str, default: &'a [String]) -> &'a [String];
+  /// Return the last value for `key`, or an error that says whether `key` was missing entirely or
+  /// given without a value.
+  fn require_value(&self, key: &str) -> Result<&String,RequireError>;
+  /// Parse every value for `key` with `FromStr`, returning a result for each value in order.
+  fn get_parsed_all<T>(&self, key: &str) -> Vec<Result<T,T::Err>> where T: FromStr;
+  /// Return a bitmask where bit `i` is set if `keys[i]` is present, for checking many boolean
+  /// options at once. Keys after the first 64 are ignored.
+  ///
+  /// ```
+  /// use argmap::ArgMapExt;
+  /// const BYTES: u64 = 1 << 0;
+  /// const WORDS: u64 = 1 << 1;
+  /// let (_args,argv) = argmap::new().booleans(&["c","w","l"]).parse(["-cl"].iter());
+  /// let flags = argv.present_set(&["c","w","l"]);
+  /// assert_eq![flags, 0b101];
+  /// assert![flags & BYTES != 0 && flags & WORDS == 0];
+  /// ```
+  fn present_set(&self, keys: &[&str]) -> u64;
+  /// Return the value for `key` if it was given exactly once, `None` if it has no values, or an
+  /// error if it was given more than once.
+  fn get_single(&self, key: &str) -> Result<Option<&String>,MultipleValuesError>;
+}
+
+/// Error returned by `ArgMapExt::require_value()`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum RequireError {
+  /// The key was not given.
+  Absent,
+  /// The key was given, but without a value.
+  EmptyValue,
+}
+
+impl std::fmt::Display for RequireError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Self::Absent => write![f, "key not given"],
+      Self::EmptyValue => write![f, "key given without a value"],
+    }
+  }
+}
+
+impl std::error::Error for RequireError {}
+
+/// Error returned by `ArgMapExt::get_single()` when a key has more than one value.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct MultipleValuesError {
+  /// The key that was looked up.
+  pub key: String,
+  /// The number of values the key had.
+  pub count: usize,
+}
+
+impl std::fmt::Display for MultipleValuesError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write![f, "expected one value for {:?}, got {}", self.key, self.count]
+  }
+}
+
+impl std::error::Error for MultipleValuesError {}
+
+impl ArgMapExt for Map {
+  fn get_all(&self, key: &str) -> &[String] {
+    self.get(key).map(|v| v.as_slice()).unwrap_or(&[])
+  }
+  fn first(&self, key: &str) -> Option<&str> {
+    self.get_all(key).first().map(|s| s.as_str())
+  }
+  fn last(&self, key: &str) -> Option<&str> {
+    self.get_all(key).last().map(|s| s.as_str())
+  }
+  fn present_set(&self, keys: &[&str]) -> u64 {
+    keys.iter().take(64).enumerate()
+      .filter(|(_,k)| self.contains_key(**k))
+      .fold(0, |mask,(i,_)| mask | 1 << i)
+  }
+  fn get_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+    self.last(key).unwrap_or(default)
+  }
+  fn get_all_or<'a>(&'a self, key: &str, default: &'a [String]) -> &'a [String] {
+    match self.get_all(key) {
+      [] => default,
+      values => values,
+    }
+  }
+  fn require_value(&self, key: &str) -> Result<&String,RequireError> {
+    self.get(key).ok_or(RequireError::Absent)?.last().ok_or(RequireError::EmptyValue)
+  }
+  fn get_parsed_all<T>(&self, key: &str) -> Vec<Result<T,T::Err>> where T: FromStr {
+    self.get_all(key).iter().map(|s| s.parse()).collect()
+  }
+  fn get_single(&self, key: &str) -> Result<Option<&String>,MultipleValuesError> {
+    match self.get_all(key) {
+      [] => Ok(None),
+      [v] => Ok(Some(v)),
+      vs => Err(MultipleValuesError { key: key.to_string(), count: vs.len() }),
+    }
+  }
+}
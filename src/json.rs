@@ -0,0 +1,62 @@
+use crate::{BoolStyle,List,Map};
+
+/// Render parsed arguments as a JSON object of the form `{"_":[...],"flags":{"key":[...]}}`,
+/// where `_` holds the positional arguments. Boolean options are rendered as empty arrays, the same
+/// as they are stored in the `Map`. Keys are sorted so the output is stable.
+///
+/// ```
+/// let (args,argv) = argmap::parse(["-x","1","--msg=hi","one"].iter());
+/// assert_eq![
+///   argmap::to_json(&args, &argv),
+///   r#"{"_":["one"],"flags":{"msg":["hi"],"x":["1"]}}"#
+/// ];
+/// ```
+pub fn to_json(args: &List, argv: &Map) -> String {
+  to_json_with(args, argv, BoolStyle::Bare)
+}
+
+/// Render parsed arguments as JSON like `to_json()`, but write boolean options as `true` instead of
+/// an empty array when `style` is `BoolStyle::True`.
+pub fn to_json_with(args: &List, argv: &Map, style: BoolStyle) -> String {
+  let mut out = String::from("{\"_\":");
+  write_array(&mut out, args);
+  out.push_str(",\"flags\":{");
+  let mut keys: Vec<&String> = argv.keys().collect();
+  keys.sort();
+  for (i,key) in keys.iter().enumerate() {
+    if i > 0 { out.push(',') }
+    write_string(&mut out, key);
+    out.push(':');
+    match (style, argv[*key].as_slice()) {
+      (BoolStyle::True, []) => out.push_str("true"),
+      (_, values) => write_array(&mut out, values),
+    }
+  }
+  out.push_str("}}");
+  out
+}
+
+fn write_array(out: &mut String, items: &[String]) {
+  out.push('[');
+  for (i,item) in items.iter().enumerate() {
+    if i > 0 { out.push(',') }
+    write_string(out, item);
+  }
+  out.push(']');
+}
+
+fn write_string(out: &mut String, s: &str) {
+  out.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if (c as u32) < 0x20 => out.push_str(&format!["\\u{:04x}", c as u32]),
+      c => out.push(c),
+    }
+  }
+  out.push('"');
+}
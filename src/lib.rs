@@ -1,27 +1,217 @@
 #![doc=include_str!("../readme.md")]
 
-use std::collections::{HashMap,HashSet};
+use std::borrow::{Borrow,Cow};
+use std::collections::{BTreeMap,HashMap,HashSet};
+use std::hash::{BuildHasher,Hash};
+use std::io;
+use std::ops::{Bound,RangeBounds};
+use std::slice::SliceIndex;
+use std::sync::atomic::{AtomicUsize,Ordering};
 
+mod ext;
+pub use ext::{ArgMapExt,MultipleValuesError,RequireError};
+mod os;
+pub use os::{parse_os_vec,OsMap};
+mod schema;
+pub use schema::{Kind,Parsed,Schema,SchemaError};
+mod shell;
+pub use shell::{tokenize,try_tokenize,TokenizeError};
+#[cfg(feature="json")] mod json;
+#[cfg(feature="json")] pub use json::{to_json,to_json_with};
+#[cfg(feature="bumpalo")] mod arena;
+
+#[derive(PartialEq,Eq)]
 pub struct ArgMap {
   pub boolean: HashSet<String>,
+  pub value: HashSet<String>,
+  pub strict: bool,
+  pub transforms: HashMap<String,Opaque<dyn Fn(String) -> String>>,
+  pub raw_after: HashSet<String>,
+  pub long_prefix: HashSet<String>,
+  pub shell_split: bool,
+  pub greedy: HashSet<String>,
+  pub trim_tokens: bool,
+  pub alias: HashMap<String,String>,
+  pub counted: HashSet<String>,
+  pub max_cluster_len: Option<usize>,
+  pub known: HashSet<String>,
+  pub no_clustering: bool,
+  pub split: HashMap<String,char>,
+  pub promote_kv: bool,
+  pub prepend_env: Option<String>,
+  pub backslash_escape: bool,
+  pub autocorrect_dashes: bool,
+  pub terminating: HashSet<String>,
+  pub rename: Option<KeyFn>,
+  pub separators: Vec<char>,
+  pub negatable: HashSet<String>,
+  pub help: HashSet<String>,
+  pub version: HashSet<String>,
+  pub file_values: HashSet<String>,
+  pub trim_file_values: bool,
+  pub on_warning: Option<WarnFn>,
+  pub long_case_insensitive: bool,
+  pub normalize_dashes: bool,
+  pub map_all: Option<ValueFn>,
+  pub interpolate_env: bool,
+  pub unknown_env_empty: bool,
+  pub positional_names: Vec<String>,
+  pub rest_key: Option<String>,
+  pub short_flag: Option<FlagFn>,
+  pub dash_values: bool,
+  pub implies: Vec<(String,Vec<String>)>,
+  pub array_syntax: bool,
+  pub list_terminator: Option<String>,
+  pub short_break: Option<FlagFn>,
+  pub strip_quotes: bool,
+  pub max_value_len: Option<usize>,
+  pub flags_before_positionals: bool,
+  pub getopt: Option<String>,
+  pub repeatable: Option<HashSet<String>>,
+  pub leftover_values: bool,
+  pub promote_keys: HashSet<String>,
+  pub keep_first: HashSet<String>,
+  pub value_pattern: Option<MatchFn>,
 }
 
 pub type Map = HashMap<String,Vec<String>>;
+pub type MapWith<S> = HashMap<String,Vec<String>,S>;
 pub type List = Vec<String>;
+pub type SortedMap = BTreeMap<String,Vec<String>>;
+pub type BorrowedMap<'a> = HashMap<Cow<'a,str>,Vec<Cow<'a,str>>>;
+pub type KeyFn = Opaque<dyn Fn(&str) -> String>;
+pub type ValueFn = Opaque<dyn Fn(String) -> String>;
+pub type WarnFn = Opaque<dyn Fn(&Warning)>;
+pub type FlagFn = Opaque<dyn Fn(&str) -> bool>;
+pub type MatchFn = Opaque<dyn Fn(&str) -> bool>;
+
+/// An item from the input with its value resolved, as returned by `parse_sequence()`.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Token<S=String> {
+  /// A positional argument.
+  Pos(S),
+  /// An option and its value, where boolean options have a value of `None`.
+  Opt { key: S, value: Option<S> },
+}
+
+impl Token<Cow<'_,str>> {
+  fn into_owned(self) -> Token {
+    match self {
+      Token::Pos(s) => Token::Pos(s.into_owned()),
+      Token::Opt { key, value } => {
+        Token::Opt { key: key.into_owned(), value: value.map(Cow::into_owned) }
+      },
+    }
+  }
+}
+
+/// A boxed value such as a closure that is compared by identity, so that `ArgMap` can be
+/// compared by its settings. Each `Opaque` gets its own id when it is built, so parsers given
+/// separately built closures are never equal, even if the closures are of the same type and
+/// capture nothing, in which case their boxes do not allocate and share an address.
+pub struct Opaque<T: ?Sized>(pub Box<T>, usize);
+
+impl<T: ?Sized> Opaque<T> {
+  fn new(value: Box<T>) -> Self {
+    static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+    Opaque(value, NEXT_ID.fetch_add(1, Ordering::Relaxed))
+  }
+}
+
+impl<T: ?Sized> PartialEq for Opaque<T> {
+  fn eq(&self, other: &Self) -> bool {
+    self.1 == other.1
+  }
+}
+
+impl<T: ?Sized> Eq for Opaque<T> {}
+
+/// How a single argument is read, as returned by `token_kind()`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum TokenKind {
+  /// `--`, after which every argument is a positional argument.
+  Separator,
+  /// A long option like `--key` or `--key=value`, or a single-dash option read as one whole key.
+  Long,
+  /// A single short option like `-x` or `-x=value`.
+  Short,
+  /// A cluster of short options like `-xvf`, possibly ending in a value like `-n5`.
+  Cluster,
+  /// The value of the pending key.
+  Value,
+  /// A positional argument.
+  Positional,
+}
 
 impl ArgMap {
   /// Create a new ArgMap instance.
+  #[allow(clippy::new_without_default)]
   pub fn new() -> Self {
     Self {
       boolean: HashSet::new(),
+      value: HashSet::new(),
+      strict: false,
+      transforms: HashMap::new(),
+      raw_after: HashSet::new(),
+      long_prefix: HashSet::new(),
+      shell_split: false,
+      greedy: HashSet::new(),
+      trim_tokens: false,
+      alias: HashMap::new(),
+      counted: HashSet::new(),
+      max_cluster_len: None,
+      known: HashSet::new(),
+      no_clustering: false,
+      split: HashMap::new(),
+      promote_kv: false,
+      prepend_env: None,
+      backslash_escape: false,
+      autocorrect_dashes: false,
+      terminating: HashSet::new(),
+      rename: None,
+      separators: vec!['='],
+      negatable: HashSet::new(),
+      help: HashSet::new(),
+      version: HashSet::new(),
+      file_values: HashSet::new(),
+      trim_file_values: false,
+      on_warning: None,
+      long_case_insensitive: false,
+      normalize_dashes: false,
+      map_all: None,
+      interpolate_env: false,
+      unknown_env_empty: false,
+      positional_names: vec![],
+      rest_key: None,
+      short_flag: None,
+      dash_values: false,
+      implies: vec![],
+      array_syntax: false,
+      list_terminator: None,
+      short_break: None,
+      strip_quotes: false,
+      max_value_len: None,
+      flags_before_positionals: false,
+      getopt: None,
+      repeatable: None,
+      leftover_values: false,
+      promote_keys: HashSet::new(),
+      keep_first: HashSet::new(),
+      value_pattern: None,
     }
   }
   /// Set a key to be treated as a boolean argument, where an argument that follows a boolean
-  /// argument will not be treated as the key's value.
+  /// argument will not be treated as the key's value. An explicit value attached with `=` (as in
+  /// `--verbose=2`) is still stored, and from `try_parse()` in strict mode it is also reported to
+  /// `on_warning()`.
   pub fn boolean<T>(mut self, key: T) -> Self where T: ToString {
     self.boolean.insert(key.to_string());
     self
   }
+  /// Set a short option to be treated as a boolean argument, like `boolean()`.
+  pub fn boolean_char(self, c: char) -> Self {
+    self.boolean(c)
+  }
   /// Set multiple keys to be treated as boolean arguments, where an argument that follows a boolean
   /// argument will not be treated as the key's value.
   pub fn booleans<T>(mut self, keys: &[T]) -> Self where T: ToString {
@@ -30,103 +220,1302 @@ impl ArgMap {
     }
     self
   }
+  /// Set a key to always take a value. In a cluster of short options, the rest of the cluster is the
+  /// value (`-xfout.txt`), or the next argument is the value if the key comes last (`-xf out.txt`).
+  /// An empty assignment like `--key=` stores an empty value and never takes the next argument.
+  pub fn value<T>(mut self, key: T) -> Self where T: ToString {
+    self.value.insert(key.to_string());
+    self
+  }
+  /// When `key` is given, also set each of `extras` that was not given, where an extra is a
+  /// boolean key like `"verbose"` or a key and value like `"log-level=debug"`. Implied keys do not
+  /// imply further keys, and a `negatable()` key undone by `--no-key` implies nothing.
+  pub fn implies(mut self, key: &str, extras: &[&str]) -> Self {
+    self.implies.push((key.to_string(), extras.iter().map(|s| s.to_string()).collect()));
+    self
+  }
+  /// Let keys registered with `value()` take the next argument as their value even when it starts
+  /// with a dash, so `--name --verbose` stores `--verbose` for `name`. A value that names a known
+  /// key is reported with `Warning::PossibleMissingValue`.
+  pub fn dash_values(mut self, yes: bool) -> Self {
+    self.dash_values = yes;
+    self
+  }
+  /// Set multiple keys to always take a value, as with `value()`.
+  pub fn values<T>(mut self, keys: &[T]) -> Self where T: ToString {
+    for key in keys.iter() {
+      self.value.insert(key.to_string());
+    }
+    self
+  }
+  /// Declare keys that the program accepts, in addition to the keys registered with other settings
+  /// such as `boolean()` or `value()`. Strict mode uses the known keys to catch mistakes.
+  pub fn known<T>(mut self, keys: &[T]) -> Self where T: ToString {
+    for key in keys.iter() {
+      self.known.insert(key.to_string());
+    }
+    self
+  }
+  /// Enable strict mode, where `try_parse()` returns an error for input that is probably a mistake
+  /// instead of storing it literally. `parse()` is not affected.
+  pub fn strict(mut self, yes: bool) -> Self {
+    self.strict = yes;
+    self
+  }
+  /// Apply `f` to each value stored for `key`, for example to trim or lowercase it. Boolean options
+  /// have no value, so `f` is not called for them.
+  pub fn map_value<T>(mut self, key: T, f: Box<dyn Fn(String) -> String>) -> Self where T: ToString {
+    self.transforms.insert(key.to_string(), Opaque::new(f));
+    self
+  }
+  /// Store every key under the name returned by `f`, for example to turn `log-level` into
+  /// `log_level`. Settings such as `boolean()` and `map_value()` still use the original names, and
+  /// `f` is called after `alias()` is resolved.
+  pub fn rename_keys(mut self, f: Box<dyn Fn(&str) -> String>) -> Self {
+    self.rename = Some(Opaque::new(f));
+    self
+  }
+  /// Set the characters that separate a key from its value in the same argument, replacing the
+  /// default of `=`. With `&['=',':']`, both `--a=1` and `--a:1` store `1` for `a`. The earliest
+  /// separator in the argument is used, so `--a:x=y` stores `x=y`.
+  pub fn kv_separators(mut self, chars: &[char]) -> Self {
+    self.separators = chars.to_vec();
+    self
+  }
+  /// Apply `f` to every value for every key, after any `map_value()` transform for the key. This
+  /// suits expansions such as `~` to the home directory.
+  pub fn map_all_values(mut self, f: Box<dyn Fn(String) -> String>) -> Self {
+    self.map_all = Some(Opaque::new(f));
+    self
+  }
+  /// Replace `$VAR` and `${VAR}` in values with the value of the environment variable `VAR`,
+  /// before any `map_value()` transforms. Write `$$` for a literal `$`. Variables that are not set
+  /// are left as written unless `unknown_env_empty()` is on.
+  pub fn interpolate_env(mut self, yes: bool) -> Self {
+    self.interpolate_env = yes;
+    self
+  }
+  /// Replace variables that are not set with an empty string in `interpolate_env()`.
+  pub fn unknown_env_empty(mut self, yes: bool) -> Self {
+    self.unknown_env_empty = yes;
+    self
+  }
+  /// Also store the first positional arguments in the map under `names`, so `cp a b` with
+  /// `&["src","dst"]` stores `["a"]` for `src` and `["b"]` for `dst`. The positional arguments are
+  /// still returned in order, and any beyond the names are only returned there.
+  pub fn positional_names<T>(mut self, names: &[T]) -> Self where T: ToString {
+    self.positional_names = names.iter().map(|n| n.to_string()).collect();
+    self
+  }
+  /// Also store the positional arguments after the ones named by `positional_names()` in the map
+  /// under `name`, so `cp a b c` with the name `src` and the rest key `files` stores `["b","c"]`
+  /// for `files`. Nothing is stored if there are no extra positional arguments.
+  pub fn rest_key(mut self, name: &str) -> Self {
+    self.rest_key = Some(name.to_string());
+    self
+  }
+  /// Decide with `f` whether an argument with a single leading dash like `-x` or `-5` is an option.
+  /// Arguments that `f` rejects are read as positional arguments or as the value of a pending key,
+  /// so `|s| !s[1..].starts_with(|c: char| c.is_ascii_digit())` leaves negative numbers alone.
+  pub fn short_flag_predicate(mut self, f: Box<dyn Fn(&str) -> bool>) -> Self {
+    self.short_flag = Some(Opaque::new(f));
+    self
+  }
+  /// Decide with `f` whether a character in a cluster of short options, passed as a one-character
+  /// string, ends the cluster so that it and the rest of the cluster are the value of the option
+  /// before it. By default any character that is not alphabetic ends the cluster, so `-c-6` stores
+  /// `-6` for `c` and `-n5` stores `5` for `n`.
+  pub fn short_break_fn(mut self, f: Box<dyn Fn(&str) -> bool>) -> Self {
+    self.short_break = Some(Opaque::new(f));
+    self
+  }
+  /// Stop parsing options after the positional argument `verb`. With `parse_with_raw()`, the
+  /// remaining input is returned unparsed. With `parse()`, it becomes positional arguments as if it
+  /// came after `--`.
+  pub fn raw_after(mut self, verb: &str) -> Self {
+    self.raw_after.insert(verb.to_string());
+    self
+  }
+  /// Set long keys that take the rest of their argument as a value without an `=`, so that
+  /// `--O2` is parsed as `--O=2` when `O` is registered.
+  pub fn long_value_prefix<T>(mut self, keys: &[T]) -> Self where T: ToString {
+    for key in keys.iter() {
+      self.long_prefix.insert(key.to_string());
+    }
+    self
+  }
+  /// Split the input of `parse_reader()` and `parse_stdin()` with shell quoting rules like
+  /// `parse_str()` instead of on whitespace alone.
+  pub fn shell_split(mut self, yes: bool) -> Self {
+    self.shell_split = yes;
+    self
+  }
+  /// Keep only the first occurrence of each of `keys` and ignore later ones, so `--color=auto
+  /// --color=never` stores `["auto"]` for `color`. A key first given without a value stays empty.
+  pub fn keep_first<T>(mut self, keys: &[T]) -> Self where T: ToString {
+    self.keep_first.extend(keys.iter().map(|k| k.to_string()));
+    self
+  }
+  /// Set a key to collect every following argument as a value until the next argument that starts
+  /// with `-` or the end of input, so `--msg a b c` stores `["a","b","c"]` for `msg`. If keys have
+  /// been declared with `known()`, only a known option or `--` ends the values, so `--msg a -x`
+  /// stores `-x` too when `x` is not known.
+  pub fn greedy<T>(mut self, key: T) -> Self where T: ToString {
+    self.greedy.insert(key.to_string());
+    self
+  }
+  /// End the values of a `greedy()` key at `token` and drop it, so `--files a b ; --verbose`
+  /// stores `["a","b"]` for `files` and goes on to read `--verbose` as an option. Unlike `--`, this
+  /// does not stop option parsing. Elsewhere `token` is an ordinary argument.
+  pub fn value_list_terminator<T>(mut self, token: T) -> Self where T: ToString {
+    self.list_terminator = Some(token.to_string());
+    self
+  }
+  /// Trim whitespace from both ends of every argument before parsing it, so `" --flag "` is read as
+  /// `--flag`. Off by default.
+  pub fn trim_tokens(mut self, yes: bool) -> Self {
+    self.trim_tokens = yes;
+    self
+  }
+  /// Store values for `key` under `canonical` instead, so that `-v` and `--verbose` can share an
+  /// entry. Settings for the key such as `boolean()` are looked up with the canonical name.
+  pub fn alias<T,U>(mut self, key: T, canonical: U) -> Self where T: ToString, U: ToString {
+    self.alias.insert(key.to_string(), canonical.to_string());
+    self
+  }
+  /// Register an `alias()` for each `(short,long)` pair, so values for the short key are stored
+  /// under the long one, as in `&[("v","verbose"),("o","output")]`.
+  pub fn auto_alias_pairs(mut self, pairs: &[(&str,&str)]) -> Self {
+    for (short,long) in pairs.iter() {
+      self.alias.insert(short.to_string(), long.to_string());
+    }
+    self
+  }
+  /// Split each value for `key` on the character `c` into separate values, so `--tags a,b` stores
+  /// `["a","b"]` when `c` is `','`. Values for other keys are not split.
+  pub fn split_on<T>(mut self, key: T, c: char) -> Self where T: ToString {
+    self.split.insert(key.to_string(), c);
+    self
+  }
+  /// Set a key to be treated as a boolean argument that counts how many times it appears, as in
+  /// `-vvv`. The count is stored as the key's only value, such as `["3"]`, and each occurrence adds
+  /// one whether or not it has a value.
+  pub fn counted<T>(mut self, key: T) -> Self where T: ToString {
+    self.counted.insert(key.to_string());
+    self
+  }
+  /// Let `--no-key` undo earlier occurrences of `key`. For a `counted()` key it subtracts one from
+  /// the count, stopping at zero, so `-vvv --no-v` counts 2. For any other key it removes the
+  /// values given so far. A key with a count of zero is left out of the result.
+  pub fn negatable<T>(mut self, key: T) -> Self where T: ToString {
+    self.negatable.insert(key.to_string());
+    self
+  }
+  /// Set boolean keys that ask for help, such as `h` and `help`. `parse_with_meta()` reports
+  /// whether any of them were given in `ParseMeta::help_requested`.
+  pub fn help_flags<T>(mut self, keys: &[T]) -> Self where T: ToString {
+    for key in keys.iter() {
+      self.help.insert(key.to_string());
+      self.boolean.insert(key.to_string());
+    }
+    self
+  }
+  /// Set boolean keys that ask for the version, such as `version`. `parse_with_meta()` reports
+  /// whether any of them were given in `ParseMeta::version_requested`.
+  pub fn version_flags<T>(mut self, keys: &[T]) -> Self where T: ToString {
+    for key in keys.iter() {
+      self.version.insert(key.to_string());
+      self.boolean.insert(key.to_string());
+    }
+    self
+  }
+  /// Set keys whose values are read from a file when they start with `@`, as in
+  /// `--data @body.json`. Files are only read by `try_parse()`, which returns an error if a file
+  /// can't be read. Write `@@` to start a value with a literal `@`.
+  pub fn file_values<T>(mut self, keys: &[T]) -> Self where T: ToString {
+    for key in keys.iter() {
+      self.file_values.insert(key.to_string());
+    }
+    self
+  }
+  /// Trim whitespace such as a trailing newline from values read with `file_values()`. Off by
+  /// default.
+  pub fn trim_file_values(mut self, yes: bool) -> Self {
+    self.trim_file_values = yes;
+    self
+  }
+  /// Call `f` with each `Warning` about input that was parsed but is probably a mistake.
+  pub fn on_warning(mut self, f: Box<dyn Fn(&Warning)>) -> Self {
+    self.on_warning = Some(Opaque::new(f));
+    self
+  }
+  /// Lowercase the keys of long options, so `--Verbose` and `--verbose` are the same key. Short
+  /// options keep their case, so `-V` and `-v` stay different. Register long keys in lowercase
+  /// when this is on.
+  pub fn long_case_insensitive(mut self, yes: bool) -> Self {
+    self.long_case_insensitive = yes;
+    self
+  }
+  /// Remove one matching pair of `"` or `'` quotes around each value, for input from a source
+  /// that left them in, so `--name='hi'` stores `hi`. Values with unbalanced quotes are kept as
+  /// they are. Positional arguments are not changed.
+  pub fn strip_quotes(mut self, yes: bool) -> Self {
+    self.strip_quotes = yes;
+    self
+  }
+  /// Drop a trailing `[]` or index like `[0]` from the keys of long options, so `--tag[]=a` and
+  /// `--tag[1]=b` both append to `tag`. Values are stored in the order they are given, whatever
+  /// their index.
+  pub fn array_syntax(mut self, yes: bool) -> Self {
+    self.array_syntax = yes;
+    self
+  }
+  /// Store keys with their inner dashes replaced by underscores, so `--log-level` is stored as
+  /// `log_level`. Settings such as `boolean()` still use the names with dashes.
+  pub fn normalize_dashes(mut self, yes: bool) -> Self {
+    self.normalize_dashes = yes;
+    self
+  }
+  /// Treat an argument with a single leading dash as one whole key like a long option, so that
+  /// `-output file` stores `file` for `output` and `-xvf` is the key `xvf` instead of a cluster.
+  pub fn no_clustering(mut self, yes: bool) -> Self {
+    self.no_clustering = yes;
+    self
+  }
+  /// Limit clusters of short options to `n` characters. A longer cluster is treated as a single key
+  /// made of the whole cluster, or is an error from `try_parse()` in strict mode.
+  pub fn max_cluster_len(mut self, n: usize) -> Self {
+    self.max_cluster_len = Some(n);
+    self
+  }
+  /// Parse short options like getopt(3) with the POSIX `optstring`, for ports of C programs. In
+  /// `"ab:c::"`, `a` is a boolean, `b` takes an argument that is either the rest of its argument
+  /// or the whole next argument even if it starts with `-`, and `c` takes an optional argument
+  /// only from the rest of its argument. Every argument that starts with `-` is read as a cluster
+  /// of short options, so `--long` is the options `-`, `l`, `o`, `n` and `g`.
+  ///
+  /// A leading `+` stops option parsing at the first positional argument. A leading `-` keeps
+  /// positional arguments in order among the options, which is also the default. A `:` after
+  /// these reports a missing argument with the code `':'` instead of `'?'`, as getopt does.
+  /// Unknown options and missing arguments are errors from `try_parse()` in strict mode, and are
+  /// otherwise stored as booleans.
+  pub fn getopt_mode(mut self, optstring: &str) -> Self {
+    self.getopt = Some(optstring.to_string());
+    self
+  }
+  /// In `parse_partial()`, hand back the argument after an unknown option with it, as its value,
+  /// unless the option has an attached value like `--key=value` or the argument starts with `-`.
+  pub fn leftover_values(mut self, yes: bool) -> Self {
+    self.leftover_values = yes;
+    self
+  }
+  /// Allow only `keys` and `counted()` keys to be given more than once. Any other key given twice
+  /// is an error from `try_parse()` in strict mode. Without strict mode, every value is stored as
+  /// usual. Call this more than once to allow more keys.
+  pub fn repeatable<T>(mut self, keys: &[T]) -> Self where T: ToString {
+    let set = self.repeatable.get_or_insert_with(HashSet::new);
+    set.extend(keys.iter().map(|k| k.to_string()));
+    self
+  }
+  /// Expect known options to come before every positional argument, as with global options before
+  /// a subcommand. A known option after a positional argument is an error from `try_parse()` in
+  /// strict mode, and is otherwise reported with `Warning::FlagAfterPositional` and stored.
+  pub fn flags_before_positionals(mut self, yes: bool) -> Self {
+    self.flags_before_positionals = yes;
+    self
+  }
+  /// Require every value to match `f`, such as `Box::new(move |v| re.is_match(v))` with a compiled
+  /// regex. A value that does not match is an error from `try_parse()` in strict mode, and is
+  /// otherwise stored as usual.
+  pub fn value_pattern(mut self, f: Box<dyn Fn(&str) -> bool>) -> Self {
+    self.value_pattern = Some(Opaque::new(f));
+    self
+  }
+  /// Limit values to `n` bytes. A longer value is cut short at the last character that fits as soon
+  /// as it is read, or is an error from `try_parse()` in strict mode before any more input is read.
+  pub fn max_value_len(mut self, n: usize) -> Self {
+    self.max_value_len = Some(n);
+    self
+  }
+  /// Store a positional argument of the form `key=value` as an option instead, like an environment
+  /// assignment, so `name=bob` stores `["bob"]` for `name`. Only arguments where `key` is an
+  /// identifier are promoted, so `a/b=c` stays positional. The key ends at the first of the
+  /// `kv_separators()`, like it does for options.
+  pub fn promote_kv_positionals(mut self, yes: bool) -> Self {
+    self.promote_kv = yes;
+    self
+  }
+  /// Promote positional arguments of the form `key=value` like `promote_kv_positionals()`, but only
+  /// for the given keys, so `name=bob` is stored for `name` while `foo=bar` stays positional.
+  pub fn promote_positional_keys<T>(mut self, keys: &[T]) -> Self where T: ToString {
+    self.promote_keys.extend(keys.iter().map(|k| k.to_string()));
+    self
+  }
+  /// Read default arguments from the environment variable `var` in `parse_env()`. The variable is
+  /// split with shell quoting rules and its arguments are parsed before the given input, like
+  /// `RUSTFLAGS` or `LESS`.
+  pub fn prepend_env(mut self, var: &str) -> Self {
+    self.prepend_env = Some(var.to_string());
+    self
+  }
+  /// Read an argument that starts with a backslash and then a dash as a literal value with the
+  /// backslash removed, so `\-weird` is the positional argument `-weird` instead of an option.
+  /// Only one backslash is removed, so `\\-weird` becomes `\-weird`.
+  pub fn backslash_escape(mut self, yes: bool) -> Self {
+    self.backslash_escape = yes;
+    self
+  }
+  /// Read a leading em dash (`—`) or en dash (`–`) as `--`, so that `—flag` pasted from a document
+  /// is parsed as `--flag`. Off by default.
+  pub fn autocorrect_dashes(mut self, yes: bool) -> Self {
+    self.autocorrect_dashes = yes;
+    self
+  }
+  /// Set keys that end option parsing like `--`, as in `--exec cmd -v`. The key takes its value as
+  /// usual if it has one, and every argument after that is a positional argument.
+  pub fn terminating_flags<T>(mut self, keys: &[T]) -> Self where T: ToString {
+    for key in keys.iter() {
+      self.terminating.insert(key.to_string());
+    }
+    self
+  }
+  /// Classify a single argument with the same rules as `parse()`, where `pending` is a key that is
+  /// still waiting for its value, as after `--file`. This does not account for state such as an
+  /// earlier `--` or a `greedy()` key.
+  pub fn token_kind(&self, token: &str, pending: Option<&str>) -> TokenKind {
+    let mut s = Cow::Borrowed(if self.trim_tokens { token.trim() } else { token });
+    if self.autocorrect_dashes {
+      s = correct_dashes(s);
+    }
+    let empty_key = self.starts_with_sep(s.strip_prefix("--").or_else(|| s.strip_prefix('-')));
+    let option = s.starts_with('-') && s != "-" && !empty_key
+      && !(self.backslash_escape && is_escaped(&s)) && self.is_short_flag(&s);
+    let dash_value = self.dash_values && pending.map(|k| self.is_value(k)).unwrap_or(false);
+    if pending.is_some() && (dash_value || !option || (!s.starts_with("--") && is_num(&s[1..]))) {
+      TokenKind::Value
+    } else if s == "--" {
+      TokenKind::Separator
+    } else if !option {
+      match self.find_sep(&s) {
+        Some((i,_)) if self.promotes(&s[..i]) => TokenKind::Long,
+        _ => TokenKind::Positional,
+      }
+    } else if s.starts_with("--") || self.no_clustering {
+      TokenKind::Long
+    } else {
+      let end = self.find_sep(&s[1..]).map(|(i,_)| i+1).unwrap_or(s.len());
+      let n = s[1..end].chars().count();
+      if self.max_cluster_len.map(|m| n > m).unwrap_or(false) {
+        TokenKind::Long
+      } else if n == 1 {
+        TokenKind::Short
+      } else {
+        TokenKind::Cluster
+      }
+    }
+  }
   /// Parse an iterator of string arguments into a 2-tuple of positional arguments and a
   /// HashMap mapping String keys to Vec<String> values.
-  pub fn parse<T>(&mut self, input: impl Iterator<Item=T>) -> (List,Map) where T: ToString {
-    let mut args: List = vec![];
-    let mut argv: Map = HashMap::new();
-    let mut key: Option<String> = None;
+  pub fn parse<T>(&self, input: impl Iterator<Item=T>) -> (List,Map) where T: ToString {
+    self.collect(self.sequence(input))
+  }
+  /// Parse like `parse()`, but build the HashMap with the hasher `S`, such as a hasher that resists
+  /// hash flooding from untrusted keys.
+  pub fn parse_with_hasher<S,T>(&self, input: impl Iterator<Item=T>) -> (List,MapWith<S>)
+  where S: BuildHasher+Default, T: ToString {
+    self.collect_with(self.sequence(input), Cow::into_owned)
+  }
+  /// Parse like `parse()`, but return only the HashMap. Positional arguments are dropped, including
+  /// everything after `--` and the arguments named by `positional_names()` or `rest_key()`.
+  pub fn parse_flags_only<T>(&self, input: impl Iterator<Item=T>) -> Map where T: ToString {
+    let mut tokens = self.sequence(input);
+    tokens.retain(|t| matches![t, Token::Opt { .. }]);
+    self.collect(tokens).1
+  }
+  /// Parse like `parse()`, but return a BTreeMap so that keys are iterated in sorted order.
+  pub fn parse_sorted<T>(&self, input: impl Iterator<Item=T>) -> (List,SortedMap)
+  where T: ToString {
+    let (args,argv) = self.parse(input);
+    (args, argv.into_iter().collect())
+  }
+  /// Parse like `parse()`, but return strings that borrow from `input` wherever they are a part of
+  /// it, such as whole positional arguments, the keys of a cluster, and the value after an `=`.
+  /// Strings made by the parser, such as a `counted()` total, an `alias()` key, or the output of a
+  /// transform, are owned.
+  pub fn parse_borrowed<'a>(&self, input: impl Iterator<Item=&'a str>)
+  -> (Vec<Cow<'a,str>>,BorrowedMap<'a>) {
+    let tokens = self.run_lenient(&mut input.map(Cow::Borrowed), Run::default(),
+      &mut ParseMeta::default());
+    self.collect_with(tokens, |s| s)
+  }
+  /// Parse like `parse()`, but first parse the arguments from the `prepend_env()` variable if it is
+  /// set. Values from `input` come after values from the variable, so `last()` prefers them. Skip
+  /// the program name from `std::env::args()` before calling this. Returns an error if the
+  /// variable ends inside of a quote, as with `try_tokenize()`.
+  pub fn parse_env<T>(&self, input: impl Iterator<Item=T>) -> Result<(List,Map),TokenizeError>
+  where T: ToString {
+    let env = match self.prepend_env.as_ref().and_then(|var| std::env::var(var).ok()) {
+      Some(s) => try_tokenize(&s)?,
+      None => vec![],
+    };
+    Ok(self.parse(env.into_iter().chain(input.map(|x| x.to_string()))))
+  }
+  /// Parse like `parse()`, but keep only the first value given for each key, so that earlier
+  /// arguments win over later ones. Boolean options map to an empty string.
+  pub fn parse_first<T>(&self, input: impl Iterator<Item=T>) -> (List,HashMap<String,String>)
+  where T: ToString {
+    let (args,argv) = self.parse(input);
+    let argv = argv.into_iter().map(|(k,v)| {
+      (k, v.into_iter().next().unwrap_or_default())
+    }).collect();
+    (args,argv)
+  }
+  /// Parse like `parse()`, but take the first argument as the program name (as with
+  /// `std::env::args()`) and return it separately instead of as the first positional argument.
+  pub fn parse_program<T>(&self, input: impl Iterator<Item=T>) -> (Option<String>,List,Map)
+  where T: ToString {
+    let mut input = input.map(|x| x.to_string());
+    let program = input.next();
+    let (args,argv) = self.parse(input);
+    (program,args,argv)
+  }
+  /// Parse an iterator of string arguments like `parse()`, but when strict mode is enabled return
+  /// an error for suspicious input.
+  pub fn try_parse<T>(&self, input: impl Iterator<Item=T>) -> Result<(List,Map),ParseError>
+  where T: ToString {
+    let run = Run { strict: self.strict, files: true, ..Run::default() };
+    let tokens = self.run(&mut input.map(owned), run, &mut ParseMeta::default())?;
+    Ok(self.collect(tokens))
+  }
+  /// Parse an iterator of string arguments into a Vec of positional arguments and options with
+  /// their resolved values, in the same order as the input.
+  pub fn parse_sequence<T>(&self, input: impl Iterator<Item=T>) -> Vec<Token> where T: ToString {
+    self.sequence(input).into_iter().map(Token::into_owned).collect()
+  }
+  fn sequence<T>(&self, input: impl Iterator<Item=T>) -> Vec<Token<Cow<'static,str>>>
+  where T: ToString {
+    self.run_lenient(&mut input.map(owned), Run::default(), &mut ParseMeta::default())
+  }
+  /// Parse into a flat list of `(key,value)` pairs in input order. Positional arguments have an
+  /// empty key and boolean options have an empty value.
+  pub fn parse_flat<T>(&self, input: impl Iterator<Item=T>) -> Vec<(String,String)>
+  where T: ToString {
+    self.parse_sequence(input).into_iter().map(|token| match token {
+      Token::Pos(s) => (String::new(), s),
+      Token::Opt { key, value } => (key, value.unwrap_or_default()),
+    }).collect()
+  }
+  /// Parse like `parse()` and also return a `ParseMeta` with diagnostic information about how the
+  /// input was interpreted.
+  pub fn parse_with_meta<T>(&self, input: impl Iterator<Item=T>) -> (List,Map,ParseMeta)
+  where T: ToString {
+    let mut meta = ParseMeta::default();
+    let tokens = self.run_lenient(&mut input.map(owned), Run::default(), &mut meta);
+    let (args,argv) = self.collect(tokens);
+    (args,argv,meta)
+  }
+  /// Parse options up to and including the first positional argument (such as a subcommand) and
+  /// return the remaining input unparsed as a third element, so it can be parsed again with a
+  /// different configuration.
+  pub fn parse_until_positional<T>(&self, input: impl Iterator<Item=T>) -> (List,Map,Vec<String>)
+  where T: ToString {
+    let mut input = input.map(|x| x.to_string());
+    let run = Run { until_positional: true, ..Run::default() };
+    let mut meta = ParseMeta::default();
+    let tokens = self.run_lenient(&mut input.by_ref().map(Cow::Owned), run, &mut meta);
+    let (args,argv) = self.collect(tokens);
+    (args, argv, input.collect())
+  }
+  /// Parse only the options with known keys (see `known()`) and return every other option verbatim
+  /// as a third element, in order, so that it can be parsed again by another parser. A cluster of
+  /// short options is known if all of its keys are. With `leftover_values()`, an unknown option
+  /// also hands back the argument after it.
+  pub fn parse_partial<T>(&self, input: impl Iterator<Item=T>) -> (List,Map,Vec<String>)
+  where T: ToString {
+    let mut input = input.map(|x| x.to_string()).peekable();
+    let (mut known,mut leftover) = (vec![],vec![]);
+    let mut breaks = vec![];
+    let mut pending: Option<String> = None;
+    while let Some(s) = input.next() {
+      if s == "--" {
+        known.push(s);
+        known.extend(input.by_ref());
+        break;
+      }
+      let kind = self.token_kind(&s, pending.as_deref());
+      pending = None;
+      let name = s.trim_start_matches('-');
+      let (end,mut attached) = match self.find_sep(name) {
+        Some((i,_)) => (i, true),
+        None => (name.len(), false),
+      };
+      let keys: Vec<String> = match kind {
+        TokenKind::Long => vec![name[..end].to_string()],
+        TokenKind::Short | TokenKind::Cluster => {
+          let mut keys: Vec<String> = vec![];
+          for c in name[..end].chars() {
+            let k = c.to_string();
+            let value_follows = keys.last().map(|p| self.is_value(p) || self.breaks_cluster(&k));
+            if value_follows == Some(true) {
+              attached = true;
+              break;
+            }
+            keys.push(k);
+          }
+          keys
+        },
+        _ => {
+          known.push(s);
+          continue;
+        },
+      };
+      if keys.iter().all(|k| self.is_known(k)) {
+        pending = keys.last().filter(|k| !attached && !self.is_boolean(k)).cloned();
+        known.push(s);
+      } else {
+        if breaks.last() != Some(&known.len()) {
+          breaks.push(known.len());
+        }
+        leftover.push(s);
+        if self.leftover_values && !attached {
+          leftover.extend(input.next_if(|v| !v.starts_with('-')));
+        }
+      }
+    }
+    let run = Run { breaks: &breaks, ..Run::default() };
+    let tokens = self.run_lenient(&mut known.into_iter().map(Cow::Owned), run,
+      &mut ParseMeta::default());
+    let (args,argv) = self.collect(tokens);
+    (args, argv, leftover)
+  }
+  /// Parse like `parse()`, but return the input that follows a `raw_after()` verb unparsed as a
+  /// third element instead of adding it to the positional arguments.
+  pub fn parse_with_raw<T>(&self, input: impl Iterator<Item=T>) -> (List,Map,Vec<String>)
+  where T: ToString {
+    let mut input = input.map(|x| x.to_string());
+    let run = Run { raw: true, ..Run::default() };
+    let mut meta = ParseMeta::default();
+    let tokens = self.run_lenient(&mut input.by_ref().map(Cow::Owned), run, &mut meta);
+    let (args,argv) = self.collect(tokens);
+    (args, argv, input.collect())
+  }
+  fn run_lenient<'a>(&self, input: &mut impl Iterator<Item=Cow<'a,str>>, run: Run,
+  meta: &mut ParseMeta) -> Vec<Token<Cow<'a,str>>> {
+    match self.run(input, run, meta) {
+      Ok(tokens) => tokens,
+      Err(_) => unreachable![],
+    }
+  }
+  fn run<'a>(&self, input: &mut impl Iterator<Item=Cow<'a,str>>, run: Run, meta: &mut ParseMeta)
+  -> Result<Vec<Token<Cow<'a,str>>>,ParseError> {
+    let strict = run.strict;
+    let mut out: Vec<Token<Cow<'a,str>>> = vec![];
+    let mut key: Option<Cow<'a,str>> = None;
     let mut dashdash = false;
-    for x in input {
-      let s = x.to_string();
+    let mut separated = false;
+    let mut greedy: Option<Cow<'a,str>> = None;
+    // indices into out of values continued by a greedy key, pushed in ascending order
+    let mut continued: Vec<usize> = vec![];
+    let mut index: usize = 0;
+    let mut positions: Vec<usize> = vec![];
+    loop {
+      if let Some(n) = self.max_value_len {
+        self.limit_values(&mut out[positions.len()..], n, strict)?;
+      }
+      positions.resize(out.len(), index.saturating_sub(1));
+      if run.until_positional && matches![out.last(), Some(Token::Pos(_))] {
+        break;
+      }
+      if let (false, Some(Token::Pos(p))) = (dashdash, out.last()) {
+        if self.raw_after.contains(p.as_ref()) {
+          if run.raw { break }
+          dashdash = true;
+          meta.passthrough_from = Some(index-1);
+        }
+      }
+      if let (false, None, Some(Token::Opt { key: k, .. })) = (dashdash, &key, out.last()) {
+        if self.terminating.contains(self.key(k)) {
+          dashdash = true;
+          meta.passthrough_from = Some(index-1);
+        }
+      }
+      let Some(mut s) = input.next() else { break };
+      index += 1;
+      if run.breaks.binary_search(&(index-1)).is_ok() {
+        if let Some(k) = key.take() {
+          self.end_pending(k, &mut out, strict)?;
+        }
+        greedy = None;
+      }
+      if self.trim_tokens {
+        s = trim(s);
+      }
       if dashdash {
-        args.push(s);
+        if separated && s == "--" {
+          self.warn(Warning::RepeatedSeparator { index: index-1 });
+        }
+        out.push(Token::Pos(s));
         continue;
       }
+      if let Some(optstring) = self.getopt.as_ref().filter(|_| s != "--" || key.is_some()) {
+        if let Some(k) = key.take() {
+          set(&mut out, k, s);
+          meta.values_from_positions.push(index-1);
+        } else if s.starts_with('-') && s != "-" {
+          key = self.getopt_cluster(optstring, &s, &mut out, strict)?;
+        } else {
+          if optstring.starts_with('+') {
+            dashdash = true;
+            meta.passthrough_from = Some(index-1);
+          }
+          out.push(Token::Pos(s));
+        }
+        continue;
+      }
+      if self.autocorrect_dashes {
+        s = correct_dashes(s);
+      }
+      let escaped = self.backslash_escape && is_escaped(&s);
+      if let Some(k) = key.take() {
+        let dashed = !escaped && s.starts_with('-') && !is_num(&s[1..]);
+        if dashed && self.terminating.contains(self.key(&k)) {
+          self.end_pending(k, &mut out, strict)?;
+          dashdash = true;
+          meta.passthrough_from = Some(index-2);
+          out.push(Token::Pos(s));
+          continue;
+        }
+        if self.dash_values && s.starts_with('-') && self.is_value(&k) {
+          let name = s.trim_start_matches('-');
+          let end = self.find_sep(name).map(|(i,_)| i).unwrap_or(name.len());
+          if !name.is_empty() && self.is_known(&name[..end]) {
+            let (key,consumed) = (k.to_string(), s.to_string());
+            self.warn(Warning::PossibleMissingValue { key, consumed });
+          }
+          set(&mut out, k, s);
+          meta.values_from_positions.push(index-1);
+          continue;
+        }
+        key = Some(k);
+      }
+      if let Some(g) = &greedy {
+        if self.list_terminator.as_deref() == Some(&*s) {
+          greedy = None;
+          continue;
+        }
+        if !self.ends_greedy(&s) {
+          if escaped { s = cut(s, 1..); }
+          continued.push(out.len());
+          meta.values_from_positions.push(index-1);
+          set(&mut out, g.clone(), s);
+          continue;
+        }
+        greedy = None;
+      }
       if s == "--" {
         dashdash = true;
+        separated = true;
+        meta.passthrough_from = Some(index-1);
+        let n = out.iter().filter(|t| matches![t, Token::Pos(_)]).count();
+        meta.separator_positional_index = Some(n);
       } else if s == "-" {
-        args.push(s);
-      } else if s.starts_with("--") {
-        if let Some(k) = &key {
-          argv.insert(k.clone(), vec![]);
-          key = None;
-        }
-        let k = s[2..].to_string();
-        if let Some(i) = k.find("=") {
-          set(&mut argv, &k[0..i].to_string(), &k[i+1..].to_string());
-        } else if self.boolean.contains(&k) {
-          set_bool(&mut argv, &k)
+        // a lone dash usually means stdin, so it can be the value of a pending key
+        if let Some(k) = key.take() {
+          set(&mut out, k, s);
+          meta.values_from_positions.push(index-1);
         } else {
-          key = Some(k);
+          out.push(Token::Pos(s));
+        }
+      } else if self.starts_with_sep(s.strip_prefix("--").or_else(|| s.strip_prefix('-'))) {
+        // an empty key is more likely a typo or a filename than an option
+        if let Some(k) = key.take() {
+          set(&mut out, k, s);
+          meta.values_from_positions.push(index-1);
+        } else {
+          out.push(Token::Pos(s));
+        }
+      } else if s.starts_with("--") {
+        if let Some(k) = key.take() {
+          self.end_pending(k, &mut out, strict)?;
+        }
+        let mut k = cut(s, 2..);
+        if self.long_case_insensitive {
+          let end = self.find_sep(&k).map(|(i,_)| i).unwrap_or(k.len());
+          if k[..end].chars().any(|c| !c.to_lowercase().eq(std::iter::once(c))) {
+            k = Cow::Owned(k[..end].to_lowercase() + &k[end..]);
+          }
+        }
+        if self.array_syntax {
+          let end = self.find_sep(&k).map(|(i,_)| i).unwrap_or(k.len());
+          let name = strip_index(&k[..end]);
+          if name.len() < end {
+            k = Cow::Owned(name.to_string() + &k[end..]);
+          }
         }
-      } else if s.starts_with("-") {
-        if let Some(k) = &key {
-          if is_num(&s[1..2]) {
-            set(&mut argv, &k, &s.to_string());
-            key = None;
+        key = self.long(k, &mut out, strict)?;
+      } else if s.starts_with("-") && self.is_short_flag(&s) {
+        if let Some(k) = key.take() {
+          if is_num(&s[1..]) {
+            set(&mut out, k, s);
+            meta.values_from_positions.push(index-1);
             continue;
           }
-          set_bool(&mut argv, &k);
-          argv.insert(k.clone(), vec![]);
-          key = None;
+          self.end_pending(k, &mut out, strict)?;
         }
-        if let Some(i) = s.find("=") {
-          let sk = s[1..i].to_string();
-          let sv = s[i+1..].to_string();
-          set(&mut argv, &sk, &sv);
-        } else {
-          let mut jump = false;
-          for i in 1..s.len()-1 {
-            let k = s[i..i+1].to_string();
-            if let Some(sk) = &key {
-              if is_num(&k) || short_break(&k) {
-                set(&mut argv, sk, &s[i..].to_string());
-                key = None;
-                jump = true;
-                break;
-              } else {
-                set_bool(&mut argv, &sk);
-              }
-              key = None;
-            }
-            if self.boolean.contains(&k) {
-              set_bool(&mut argv, &k);
-            } else {
-              key = Some(k);
-            }
+        if self.no_clustering {
+          key = self.long(cut(s, 1..), &mut out, strict)?;
+          continue;
+        }
+        let (end,eq) = match self.find_sep(&s[1..]) {
+          Some((i,j)) => (i+1, Some(j+1)),
+          None => (s.len(), None),
+        };
+        if strict && end > 2 && self.is_known(&s[1..end]) {
+          return Err(ParseError::LikelyLongFlag { token: s.into_owned() });
+        }
+        if self.max_cluster_len.map(|n| s[1..end].chars().count() > n).unwrap_or(false) {
+          if strict {
+            return Err(ParseError::ClusterTooLong { token: s.into_owned() });
           }
-          if jump { continue }
-          let k = s[s.len()-1..].to_string();
-          if let Some(sk) = &key {
-            if self.boolean.contains(&k) {
-              set_bool(&mut argv, sk);
-              set_bool(&mut argv, &k);
-            } else if is_num(&k) || short_break(&k) {
-              set(&mut argv, sk, &k);
-              key = None;
-            } else {
-              set_bool(&mut argv, sk);
-              key = Some(k);
+          let k = part(&s, 1..end);
+          if let Some(j) = eq {
+            set(&mut out, k, part(&s, j..));
+          } else if self.is_boolean(&k) {
+            set_bool(&mut out, k);
+          } else {
+            key = Some(k);
+          }
+          continue;
+        }
+        let allowlist = strict && end > 2 && !self.known.is_empty()
+          && s[1..end].chars().any(|c| self.is_known(&c.to_string()));
+        let cluster: Vec<(usize,char)> = s[..end].char_indices().skip(1).collect();
+        let (last,rest) = cluster.split_last().expect("a short option has a key");
+        let mut jump = false;
+        for &(i,c) in rest {
+          let k = part(&s, i..i+c.len_utf8());
+          if let Some(sk) = key.take() {
+            if self.breaks_cluster(&k) {
+              set(&mut out, sk, part(&s, i..));
+              jump = true;
+              break;
             }
-          } else if self.boolean.contains(&k) {
-            set_bool(&mut argv, &k);
+            set_bool(&mut out, sk);
+          }
+          if allowlist { self.check_cluster(&s, &k)? }
+          if self.is_value(&k) {
+            set(&mut out, k, part(&s, i+c.len_utf8()..));
+            jump = true;
+            break;
+          } else if self.is_boolean(&k) {
+            set_bool(&mut out, k);
           } else {
             key = Some(k);
           }
         }
-      } else if let Some(k) = key {
-        set(&mut argv, &k, &s);
-        key = None;
+        if jump { continue }
+        let k = part(&s, last.0..end);
+        if let Some(sk) = key.take() {
+          if !self.is_boolean(&k) && self.breaks_cluster(&k) {
+            set(&mut out, sk, part(&s, last.0..));
+            continue;
+          }
+          set_bool(&mut out, sk);
+        }
+        if allowlist { self.check_cluster(&s, &k)? }
+        if let Some(j) = eq {
+          let v = part(&s, j..);
+          if strict { check_value(&k, &v, &self.separators)? }
+          self.check_boolean_value(&k, &v, strict);
+          set(&mut out, k, v);
+        } else if self.is_boolean(&k) {
+          set_bool(&mut out, k);
+        } else {
+          key = Some(k);
+        }
       } else {
-        args.push(s);
+        if escaped { s = cut(s, 1..); }
+        if let Some(k) = key.take() {
+          if self.greedy.contains(self.key(&k)) {
+            greedy = Some(k.clone());
+          }
+          set(&mut out, k, s);
+          meta.values_from_positions.push(index-1);
+        } else if let Some((i,j)) = self.find_sep(&s).filter(|(i,_)| self.promotes(&s[..*i])) {
+          set(&mut out, part(&s, ..i), part(&s, j..));
+        } else {
+          out.push(Token::Pos(s));
+        }
       }
     }
     if let Some(k) = key {
-      set_bool(&mut argv, &k);
+      self.end_pending(k, &mut out, strict)?;
+    }
+    if let Some(n) = self.max_value_len {
+      self.limit_values(&mut out[positions.len()..], n, strict)?;
+    }
+    positions.resize(out.len(), index.saturating_sub(1));
+    if self.flags_before_positionals {
+      let first = out.iter().position(|t| matches![t, Token::Pos(_)]).unwrap_or(out.len());
+      for (i,token) in out.iter().enumerate().skip(first) {
+        let Token::Opt { key, .. } = token else { continue };
+        if continued.binary_search(&i).is_ok() || !self.is_known(key) { continue }
+        if strict {
+          return Err(ParseError::FlagAfterPositional { key: key.to_string() });
+        }
+        self.warn(Warning::FlagAfterPositional { key: key.to_string(), index: positions[i] });
+      }
+    }
+    meta.last_values.extend(self.last_values(&out));
+    for (i,token) in out.iter().enumerate() {
+      if let Token::Pos(_) = token {
+        meta.positional_count += 1;
+      }
+      if let Token::Opt { key, value } = token {
+        if value.is_some() {
+          meta.value_indices.entry(self.stored_key(key)).or_default().push(positions[i]);
+        }
+        if continued.binary_search(&i).is_err() {
+          meta.flag_count += 1;
+          if value.is_some() {
+            meta.valued_flag_count += 1;
+          }
+          let n = meta.occurrences.entry(self.stored_key(key)).or_default();
+          *n += 1;
+          if strict && *n == 2 && !self.is_repeatable(key) {
+            return Err(ParseError::Repeated { key: self.stored_key(key) });
+          }
+          if let Some(canonical) = self.alias.get(key.as_ref()) {
+            meta.alias_hits.push((key.to_string(), canonical.clone()));
+          }
+        }
+      }
+    }
+    // a counted key is expected to mix --key and --no-key, since --no-key only lowers the count
+    let mut negatable: Vec<&String> =
+      self.negatable.iter().filter(|k| !self.counted.contains(*k)).collect();
+    negatable.sort();
+    for k in negatable {
+      let keys = || out.iter().filter_map(|t| match t {
+        Token::Opt { key, .. } => Some(key.as_ref()),
+        Token::Pos(_) => None,
+      });
+      let negated = format!["no-{}", k];
+      if keys().any(|key| self.key(key) == k) && keys().any(|key| key == negated) {
+        self.warn(Warning::Contradiction { key: k.clone() });
+      }
+    }
+    let occurrences = &meta.occurrences;
+    let given = |keys: &HashSet<String>| {
+      keys.iter().any(|k| occurrences.contains_key(&self.stored_key(k)))
+    };
+    let (help,version) = (given(&self.help), given(&self.version));
+    meta.help_requested = help;
+    meta.version_requested = version;
+    if run.files {
+      self.read_file_values(&mut out)?;
+      if let Some(n) = self.max_value_len {
+        self.limit_values(&mut out, n, strict)?;
+      }
+    }
+    if let Some(f) = self.value_pattern.as_ref().filter(|_| strict) {
+      for token in out.iter() {
+        let Token::Opt { key, value: Some(v) } = token else { continue };
+        if !(f.0)(v) {
+          return Err(ParseError::PatternMismatch { key: key.to_string(), value: v.to_string() });
+        }
+      }
+    }
+    Ok(self.finish(out))
+  }
+  fn read_file_values(&self, tokens: &mut [Token<Cow<str>>]) -> Result<(),ParseError> {
+    for token in tokens.iter_mut() {
+      let Token::Opt { key, value: Some(v) } = token else { continue };
+      if !self.file_values.contains(self.key(key)) { continue }
+      if v.starts_with("@@") {
+        *v = cut(std::mem::take(v), 1..);
+      } else if let Some(path) = v.strip_prefix('@') {
+        let contents = std::fs::read_to_string(path).map_err(|e| ParseError::FileValue {
+          key: key.to_string(),
+          path: path.to_string(),
+          kind: e.kind(),
+        })?;
+        *v = Cow::Owned(if self.trim_file_values { contents.trim().to_string() } else { contents });
+      }
+    }
+    Ok(())
+  }
+  fn limit_values(&self, tokens: &mut [Token<Cow<str>>], n: usize, strict: bool)
+  -> Result<(),ParseError> {
+    for token in tokens.iter_mut() {
+      let Token::Opt { key, value: Some(v) } = token else { continue };
+      if v.len() <= n { continue }
+      if strict {
+        return Err(ParseError::ValueTooLong { key: key.to_string(), len: v.len() });
+      }
+      let mut end = n;
+      while !v.is_char_boundary(end) {
+        end -= 1;
+      }
+      *v = cut(std::mem::take(v), ..end);
+    }
+    Ok(())
+  }
+  fn getopt_cluster<'a>(&self, optstring: &str, s: &Cow<'a,str>, out: &mut Vec<Token<Cow<'a,str>>>,
+  strict: bool) -> Result<Option<Cow<'a,str>>,ParseError> {
+    let spec = optstring.strip_prefix(['+','-']).unwrap_or(optstring);
+    let spec = spec.strip_prefix(':').unwrap_or(spec);
+    for (i,c) in s.char_indices().skip(1) {
+      let k = part(s, i..i+c.len_utf8());
+      let rest = i+c.len_utf8()..s.len();
+      let arg = match spec.find(c).filter(|_| c != ':') {
+        Some(j) => &spec[j+c.len_utf8()..],
+        None if strict => return Err(ParseError::InvalidOption { opt: c }),
+        None => "",
+      };
+      if arg.starts_with("::") {
+        if rest.is_empty() { set_bool(out, k) } else { set(out, k, part(s, rest)) }
+        return Ok(None);
+      } else if arg.starts_with(':') {
+        if rest.is_empty() { return Ok(Some(k)) }
+        set(out, k, part(s, rest));
+        return Ok(None);
+      }
+      set_bool(out, k);
+    }
+    Ok(None)
+  }
+  fn end_pending<'a>(&self, k: Cow<'a,str>, out: &mut Vec<Token<Cow<'a,str>>>, strict: bool)
+  -> Result<(),ParseError> {
+    if let Some(optstring) = self.getopt.as_ref().filter(|_| strict) {
+      let spec = optstring.strip_prefix(['+','-']).unwrap_or(optstring);
+      let code = if spec.starts_with(':') { ':' } else { '?' };
+      let opt = k.chars().next().unwrap_or_default();
+      return Err(ParseError::MissingArgument { opt, code });
+    }
+    if strict && self.is_value(&k) {
+      return Err(ParseError::MissingValue { key: k.into_owned() });
+    }
+    set_bool(out, k);
+    Ok(())
+  }
+  fn warn(&self, warning: Warning) {
+    if let Some(f) = &self.on_warning {
+      (f.0)(&warning);
+    }
+  }
+  fn check_boolean_value(&self, k: &str, v: &str, strict: bool) {
+    if strict && self.boolean.contains(self.key(k)) {
+      self.warn(Warning::ValueOnBoolean { key: k.to_string(), value: v.to_string() });
+    }
+  }
+  fn ends_greedy(&self, s: &str) -> bool {
+    if !s.starts_with('-') {
+      return false;
+    }
+    if self.known.is_empty() || s == "--" {
+      return true;
+    }
+    let k = match s.strip_prefix("--") {
+      Some(k) => k[..self.find_sep(k).map(|(i,_)| i).unwrap_or(k.len())].to_string(),
+      None => s[1..].chars().next().map(|c| c.to_string()).unwrap_or_default(),
+    };
+    self.is_known(&k)
+  }
+  fn check_cluster(&self, token: &str, k: &str) -> Result<(),ParseError> {
+    match k.chars().next() {
+      Some(c) if !self.is_known(k) => {
+        Err(ParseError::UnknownCluster { token: token.to_string(), bad_char: c })
+      },
+      _ => Ok(()),
+    }
+  }
+  fn long<'a>(&self, k: Cow<'a,str>, out: &mut Vec<Token<Cow<'a,str>>>, strict: bool)
+  -> Result<Option<Cow<'a,str>>,ParseError> {
+    let negated = k.strip_prefix("no-").map(|n| self.key(n));
+    if let Some(n) = negated.filter(|n| self.negatable.contains(*n)) {
+      let renamed = (n != &k[3..]).then(|| format!["no-{}", n]);
+      set_bool(out, renamed.map(Cow::Owned).unwrap_or(k));
+    } else if let Some(p) = self.find_long_prefix(&k).map(|p| p.len()) {
+      set(out, part(&k, ..p), part(&k, p..));
+    } else if let Some((i,j)) = self.find_sep(&k) {
+      if strict { check_value(&k[0..i], &k[j..], &self.separators)? }
+      self.check_boolean_value(&k[0..i], &k[j..], strict);
+      set(out, part(&k, ..i), part(&k, j..));
+    } else if self.is_boolean(&k) {
+      set_bool(out, k)
+    } else {
+      return Ok(Some(k));
+    }
+    Ok(None)
+  }
+  fn find_long_prefix(&self, k: &str) -> Option<&str> {
+    self.long_prefix.iter()
+      .filter(|p| k.len() > p.len() && k.starts_with(p.as_str()))
+      .filter(|p| !self.starts_with_sep(Some(&k[p.len()..])))
+      .max_by_key(|p| p.len())
+      .map(|p| p.as_str())
+  }
+  fn find_sep(&self, s: &str) -> Option<(usize,usize)> {
+    s.char_indices()
+      .find(|(_,c)| self.separators.contains(c))
+      .map(|(i,c)| (i, i+c.len_utf8()))
+  }
+  fn starts_with_sep(&self, s: Option<&str>) -> bool {
+    s.map(|s| s.starts_with(self.separators.as_slice())).unwrap_or(false)
+  }
+  fn key<'a>(&'a self, k: &'a str) -> &'a str {
+    self.alias.get(k).map(|c| c.as_str()).unwrap_or(k)
+  }
+  /// Like `stored_key()`, but keep `k` as it is if no setting changes it.
+  fn stored_key_cow<'a>(&self, k: Cow<'a,str>) -> Cow<'a,str> {
+    if self.alias.contains_key(k.as_ref()) || self.normalize_dashes || self.rename.is_some() {
+      Cow::Owned(self.stored_key(&k))
+    } else {
+      k
+    }
+  }
+  fn stored_key(&self, k: &str) -> String {
+    let mut k = self.key(k).to_string();
+    if self.normalize_dashes {
+      let inner = k.trim_matches('-');
+      let start = k.len() - k.trim_start_matches('-').len();
+      let inner = inner.replace('-', "_");
+      k.replace_range(start..start+inner.len(), &inner);
+    }
+    match &self.rename {
+      Some(f) => (f.0)(&k),
+      None => k,
+    }
+  }
+  fn is_boolean(&self, k: &str) -> bool {
+    let k = self.key(k);
+    self.boolean.contains(k) || self.counted.contains(k)
+  }
+  fn is_value(&self, k: &str) -> bool {
+    self.value.contains(self.key(k))
+  }
+  fn promotes(&self, k: &str) -> bool {
+    self.promote_kv && is_ident(k) || self.promote_keys.contains(self.key(k))
+  }
+  fn is_repeatable(&self, k: &str) -> bool {
+    let k = self.key(k);
+    match &self.repeatable {
+      Some(keys) => keys.contains(k) || self.counted.contains(k),
+      None => true,
+    }
+  }
+  fn breaks_cluster(&self, k: &str) -> bool {
+    match &self.short_break {
+      Some(f) => (f.0)(k),
+      None => is_num(k) || short_break(k),
+    }
+  }
+  fn is_short_flag(&self, s: &str) -> bool {
+    s.starts_with("--") || self.short_flag.as_ref().map(|f| (f.0)(s)).unwrap_or(true)
+  }
+  fn is_known(&self, k: &str) -> bool {
+    let k = self.key(k);
+    self.known.contains(k) || self.boolean.contains(k) || self.value.contains(k)
+      || self.counted.contains(k) || self.greedy.contains(k) || self.alias.values().any(|c| c == k)
+  }
+  /// Keys from `tokens` that are still set once `--no-key` and counts have been applied.
+  // stored `no-key` keys mapped to the stored `negatable()` key they undo
+  fn negations(&self) -> HashMap<String,String> {
+    self.negatable.iter()
+      .map(|k| (self.stored_key(&format!["no-{}", k]), self.stored_key(k)))
+      .collect()
+  }
+  // the value of the occurrence that decides each stored key, resolving negation, counting and
+  // keep_first() like collect_with(), for ParseMeta::last_values
+  fn last_values(&self, tokens: &[Token<Cow<str>>]) -> HashMap<String,Option<String>> {
+    let mut last: HashMap<String,Option<String>> = HashMap::new();
+    let mut counts: HashMap<String,usize> = HashMap::new();
+    let counted: HashSet<String> = self.counted.iter().map(|k| self.stored_key(k)).collect();
+    let negated = self.negations();
+    let first: HashSet<String> = self.keep_first.iter().map(|k| self.stored_key(k)).collect();
+    for token in tokens {
+      let Token::Opt { key, value } = token else { continue };
+      let key = self.stored_key(key);
+      if let Some(k) = negated.get(&key) {
+        let n = counts.entry(k.clone()).or_default();
+        *n = n.saturating_sub(1);
+        if *n == 0 || !counted.contains(k) {
+          last.remove(k);
+        }
+      } else if !first.contains(&key) || !last.contains_key(&key) {
+        *counts.entry(key.clone()).or_default() += 1;
+        last.insert(key, value.as_ref().map(|v| v.to_string()));
+      }
+    }
+    last
+  }
+  fn settled_keys<'a>(&'a self, tokens: &'a [Token<Cow<str>>]) -> HashSet<&'a str> {
+    let mut counts: HashMap<&str,usize> = HashMap::new();
+    for token in tokens {
+      if let Token::Opt { key, .. } = token {
+        let key: &str = key;
+        let negated = key.strip_prefix("no-").filter(|n| self.negatable.contains(*n));
+        let k = self.key(negated.unwrap_or(key));
+        let n = counts.entry(k).or_default();
+        *n = match negated {
+          Some(_) if self.counted.contains(k) => n.saturating_sub(1),
+          Some(_) => 0,
+          None => n.saturating_add(1),
+        };
+      }
+    }
+    counts.into_iter().filter(|(_,n)| *n > 0).map(|(k,_)| k).collect()
+  }
+  fn finish<'a>(&self, mut tokens: Vec<Token<Cow<'a,str>>>) -> Vec<Token<Cow<'a,str>>> {
+    let given: HashSet<&str> = tokens.iter().filter_map(|t| match t {
+      Token::Opt { key, .. } => Some(self.key(key)),
+      Token::Pos(_) => None,
+    }).collect();
+    let settled = self.settled_keys(&tokens);
+    let mut implied = vec![];
+    for (key,extras) in self.implies.iter().filter(|(k,_)| settled.contains(self.key(k))) {
+      for extra in extras {
+        let (k,v) = match self.find_sep(extra) {
+          Some((i,j)) => (&extra[..i], Some(extra[j..].to_string())),
+          None => (extra.as_str(), None),
+        };
+        let present = implied.iter().any(|t| matches![t, Token::Opt { key, .. } if *key == k]);
+        if !given.contains(self.key(k)) && !present && k != key {
+          implied.push(Token::Opt { key: Cow::Owned(k.to_string()), value: v.map(Cow::Owned) });
+        }
+      }
+    }
+    tokens.extend(implied);
+    let mut out = vec![];
+    for token in tokens {
+      match token {
+        Token::Opt { key, value: Some(v) } => {
+          let v = if self.strip_quotes { unquote(v) } else { v };
+          let canonical = self.key(&key);
+          let values = match self.split.get(canonical) {
+            Some(c) => split(&v, *c),
+            None => vec![v],
+          };
+          let transform = self.transforms.get(canonical);
+          let stored = self.stored_key_cow(key);
+          for v in values {
+            let v = match self.interpolate_env && v.contains('$') {
+              true => Cow::Owned(interpolate(&v, self.unknown_env_empty)),
+              false => v,
+            };
+            let v = match transform {
+              Some(f) => Cow::Owned((f.0)(v.into_owned())),
+              None => v,
+            };
+            let v = match &self.map_all {
+              Some(f) => Cow::Owned((f.0)(v.into_owned())),
+              None => v,
+            };
+            out.push(Token::Opt { key: stored.clone(), value: Some(v) });
+          }
+        },
+        Token::Opt { key, value: None } => {
+          out.push(Token::Opt { key: self.stored_key_cow(key), value: None });
+        },
+        token => out.push(token),
+      }
+    }
+    out
+  }
+  fn collect(&self, tokens: Vec<Token<Cow<str>>>) -> (List,Map) {
+    self.collect_with(tokens, Cow::into_owned)
+  }
+  /// Collect tokens into positional arguments and a map, converting each string with `conv`.
+  fn collect_with<'a,K,S,F>(&self, tokens: Vec<Token<Cow<'a,str>>>, conv: F)
+  -> (Vec<K>,HashMap<K,Vec<K>,S>)
+  where K: Clone+Eq+Hash+Borrow<str>, S: BuildHasher+Default, F: Fn(Cow<'a,str>) -> K {
+    let mut args: Vec<K> = vec![];
+    let mut argv: HashMap<K,Vec<K>,S> = HashMap::default();
+    let mut counts: HashMap<K,usize> = HashMap::new();
+    let counted: HashSet<String> = self.counted.iter().map(|k| self.stored_key(k)).collect();
+    let negated = self.negations();
+    let first: HashSet<String> = self.keep_first.iter().map(|k| self.stored_key(k)).collect();
+    for token in tokens {
+      match token {
+        Token::Pos(s) => args.push(conv(s)),
+        Token::Opt { key, .. } if negated.contains_key(key.as_ref()) => {
+          let key = negated[key.as_ref()].as_str();
+          if counted.contains(key) {
+            if let Some(n) = counts.get_mut(key) {
+              *n = n.saturating_sub(1);
+            }
+          } else {
+            argv.remove(key);
+          }
+        },
+        Token::Opt { key, .. } if counted.contains(key.as_ref()) => {
+          let n = counts.entry(conv(key)).or_default();
+          *n = n.saturating_add(1);
+        },
+        Token::Opt { key, value } if first.contains(key.as_ref()) => {
+          argv.entry(conv(key)).or_insert_with(|| value.map(&conv).into_iter().collect());
+        },
+        Token::Opt { key, value: Some(v) } => argv.entry(conv(key)).or_default().push(conv(v)),
+        Token::Opt { key, value: None } => { argv.entry(conv(key)).or_default(); },
+      }
+    }
+    for (key,n) in counts.into_iter().filter(|(_,n)| *n > 0) {
+      argv.insert(key, vec![conv(Cow::Owned(n.to_string()))]);
+    }
+    for (name,arg) in self.positional_names.iter().zip(args.iter()) {
+      argv.entry(conv(Cow::Owned(name.clone()))).or_default().push(arg.clone());
+    }
+    if let Some(rest) = &self.rest_key {
+      if args.len() > self.positional_names.len() {
+        let rest_args = args[self.positional_names.len()..].iter().cloned();
+        argv.entry(conv(Cow::Owned(rest.clone()))).or_default().extend(rest_args);
+      }
     }
     (args,argv)
   }
+  /// Split a string into arguments with shell quoting rules (see `tokenize()`) and parse them.
+  /// Returns an error if the string ends inside of a quote, as with `try_tokenize()`.
+  pub fn parse_str(&self, s: &str) -> Result<(List,Map),TokenizeError> {
+    Ok(self.parse(try_tokenize(s)?.iter()))
+  }
+  /// Read all of `r` and parse its whitespace-separated contents as arguments, or split it like
+  /// `parse_str()` if `shell_split()` is enabled. With `shell_split()`, input that ends inside of a
+  /// quote is an error of kind `InvalidData`.
+  pub fn parse_reader<R>(&self, mut r: R) -> io::Result<(List,Map)> where R: io::Read {
+    let mut buf = String::new();
+    r.read_to_string(&mut buf)?;
+    if self.shell_split {
+      self.parse_str(&buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    } else {
+      Ok(self.parse(buf.split_whitespace()))
+    }
+  }
+  /// Read all of `r` and parse its NUL-separated contents as arguments, like `xargs -0`.
+  pub fn parse_reader_nul<R>(&self, mut r: R) -> io::Result<(List,Map)> where R: io::Read {
+    let mut buf = vec![];
+    r.read_to_end(&mut buf)?;
+    self.try_parse_bytes(&buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+  }
+  /// Read all of stdin and parse its whitespace-separated contents as arguments.
+  pub fn parse_stdin(&self) -> io::Result<(List,Map)> {
+    self.parse_reader(io::stdin())
+  }
+  /// Read all of stdin and parse its NUL-separated contents as arguments, like `xargs -0`.
+  pub fn parse_stdin_nul(&self) -> io::Result<(List,Map)> {
+    self.parse_reader_nul(io::stdin())
+  }
+  /// Parse a buffer of NUL-separated arguments, like the contents of `/proc/<pid>/cmdline`.
+  /// Each argument is decoded as UTF-8 lossily. A trailing NUL does not produce an extra argument.
+  pub fn parse_bytes(&self, input: &[u8]) -> (List,Map) {
+    self.parse(split_nul(input).map(String::from_utf8_lossy))
+  }
+  /// Parse a buffer of NUL-separated arguments like `parse_bytes()`, but return an error if any
+  /// argument is not valid UTF-8.
+  pub fn try_parse_bytes(&self, input: &[u8]) -> Result<(List,Map),std::str::Utf8Error> {
+    let items = split_nul(input).map(std::str::from_utf8).collect::<Result<Vec<&str>,_>>()?;
+    Ok(self.parse(items.into_iter()))
+  }
 }
 
 /// Create a new ArgMap instance.
@@ -140,24 +1529,473 @@ pub fn parse<T>(input: impl Iterator<Item=T>) -> (List,Map) where T: ToString {
   ArgMap::new().parse(input)
 }
 
+/// Split a string into arguments with shell quoting rules (see `tokenize()`) and parse them.
+/// Returns an error if the string ends inside of a quote, as with `try_tokenize()`.
+pub fn parse_str(s: &str) -> Result<(List,Map),TokenizeError> {
+  ArgMap::new().parse_str(s)
+}
+
+/// Parse a buffer of NUL-separated arguments, like the contents of `/proc/<pid>/cmdline`.
+/// Each argument is decoded as UTF-8 lossily. A trailing NUL does not produce an extra argument.
+pub fn parse_bytes(input: &[u8]) -> (List,Map) {
+  ArgMap::new().parse_bytes(input)
+}
+
+/// Parse a buffer of NUL-separated arguments like `parse_bytes()`, but return an error if any
+/// argument is not valid UTF-8.
+pub fn try_parse_bytes(input: &[u8]) -> Result<(List,Map),std::str::Utf8Error> {
+  ArgMap::new().try_parse_bytes(input)
+}
+
+/// Parse options up to and including the first positional argument (such as a subcommand) and
+/// return the remaining input unparsed as a third element.
+pub fn parse_until_positional<T>(input: impl Iterator<Item=T>) -> (List,Map,Vec<String>)
+where T: ToString {
+  ArgMap::new().parse_until_positional(input)
+}
+
+/// Parse like `parse()`, but take the first argument as the program name (as with
+/// `std::env::args()`) and return it separately instead of as the first positional argument.
+pub fn parse_program<T>(input: impl Iterator<Item=T>) -> (Option<String>,List,Map)
+where T: ToString {
+  ArgMap::new().parse_program(input)
+}
+
+/// Parse like `parse()` and also return a `ParseMeta` with diagnostic information about how the
+/// input was interpreted.
+pub fn parse_with_meta<T>(input: impl Iterator<Item=T>) -> (List,Map,ParseMeta) where T: ToString {
+  ArgMap::new().parse_with_meta(input)
+}
+
+/// Split positional arguments at the first `sentinel`, such as `to` in `cp a b to dir`, returning
+/// the arguments before and after it. If `sentinel` is absent, every argument is in the first list.
+pub fn split_positionals(args: &List, sentinel: &str) -> (List,List) {
+  match args.iter().position(|s| s == sentinel) {
+    Some(i) => (args[..i].to_vec(), args[i+1..].to_vec()),
+    None => (args.clone(), vec![]),
+  }
+}
+
+/// Parse every positional argument with `FromStr`, returning a result for each argument in order.
+/// Use `parse_program()` to leave out the program name.
+pub fn parse_positionals<T>(args: &List) -> Vec<Result<T,T::Err>> where T: std::str::FromStr {
+  args.iter().map(|s| s.parse()).collect()
+}
+
+/// Format parsed arguments as `args=[...]` and `argv={...}` on two lines, with the keys of `argv`
+/// sorted so the output is the same from one run to the next.
+pub fn debug_format(args: &List, argv: &Map) -> String {
+  let sorted: BTreeMap<&String,&Vec<String>> = argv.iter().collect();
+  format!["args={:?}\nargv={:?}", args, sorted]
+}
+
+/// An owned item of parsed arguments, as returned by `into_entries()`.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Entry {
+  /// A positional argument.
+  Positional(String),
+  /// A key and all of its values.
+  Flag(String,Vec<String>),
+}
+
+/// Consume parsed arguments and yield the positional arguments in order, followed by each key with
+/// its values in no particular order, without cloning.
+pub fn into_entries(args: List, argv: Map) -> impl Iterator<Item=Entry> {
+  args.into_iter().map(Entry::Positional)
+    .chain(argv.into_iter().map(|(k,v)| Entry::Flag(k,v)))
+}
+
+/// A subcommand and its arguments, as returned by `command_split()`.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct CommandInvocation {
+  /// The name of the command, which was the first positional argument.
+  pub name: String,
+  /// The positional arguments after the name.
+  pub args: List,
+  /// The options, unchanged.
+  pub argv: Map,
+}
+
+/// Split parsed arguments into a command name, taken from the first positional argument, and the
+/// rest of the arguments, for dispatching subcommands. Skip the program name first, as with
+/// `parse_program()`. Returns `None` if there are no positional arguments.
+pub fn command_split(mut args: List, argv: Map) -> Option<CommandInvocation> {
+  if args.is_empty() {
+    return None;
+  }
+  let name = args.remove(0);
+  Some(CommandInvocation { name, args, argv })
+}
+
+/// How boolean options, which have no values, are written by `to_argv()` and `to_json_with()`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum BoolStyle {
+  /// Write the key alone, as `--flag`, or as an empty array in JSON.
+  #[default]
+  Bare,
+  /// Write an explicit true value, as `--flag=true`, or as `true` in JSON.
+  True,
+}
+
+/// Turn parsed arguments back into a list of arguments that parse to the same result. Options come
+/// first in sorted order with their values attached by `=`, then `--` and the positional arguments.
+/// Keys with one character are written with a single dash.
+pub fn to_argv(args: &List, argv: &Map, style: BoolStyle) -> Vec<String> {
+  let mut out = vec![];
+  let mut keys: Vec<&String> = argv.keys().collect();
+  keys.sort();
+  for key in keys {
+    let dashes = if key.chars().count() == 1 { "-" } else { "--" };
+    let values = &argv[key];
+    if values.is_empty() {
+      match style {
+        BoolStyle::Bare => out.push(format!["{}{}", dashes, key]),
+        BoolStyle::True => out.push(format!["{}{}=true", dashes, key]),
+      }
+    }
+    for value in values {
+      out.push(format!["{}{}={}", dashes, key, value]);
+    }
+  }
+  if !args.is_empty() {
+    out.push("--".to_string());
+    out.extend(args.iter().cloned());
+  }
+  out
+}
+
+fn split_nul(input: &[u8]) -> impl Iterator<Item=&[u8]> {
+  let input = input.strip_suffix(&[0]).unwrap_or(input);
+  input.split(|b| *b == 0).filter(move |_| !input.is_empty())
+}
+
+/// Diagnostic information about a parse, returned by `parse_with_meta()`. Indexes count from 0
+/// over the input iterator.
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub struct ParseMeta {
+  /// Index of the argument (`--`, a `raw_after()` verb, or a `terminating_flags()` key or its
+  /// value) after which every argument was treated as a positional.
+  pub passthrough_from: Option<usize>,
+  /// Number of times each key appeared in the input, whether or not it had a value.
+  pub occurrences: HashMap<String,usize>,
+  /// Indexes of the arguments that were stored as the value of the option before them, as in
+  /// `--file foo`, instead of being positional arguments or options themselves.
+  pub values_from_positions: Vec<usize>,
+  /// Each key that was given as an `alias()`, paired with the canonical key it was stored under, in
+  /// input order.
+  pub alias_hits: Vec<(String,String)>,
+  /// Whether any of the `help_flags()` keys were given.
+  pub help_requested: bool,
+  /// Whether any of the `version_flags()` keys were given.
+  pub version_requested: bool,
+  /// Indexes of the arguments that held each value of each key, in order. A value split by
+  /// `split_on()` has one index.
+  pub value_indices: HashMap<String,Vec<usize>>,
+  /// Index into the positional arguments of the first one after `--`, so `a b -- c d` gives `2`.
+  /// It is the number of positional arguments if nothing came after `--`.
+  pub separator_positional_index: Option<usize>,
+  /// Number of options given, counting each key of a cluster and each repeat of a key.
+  pub flag_count: usize,
+  /// Number of positional arguments.
+  pub positional_count: usize,
+  /// Number of the options in `flag_count` that were given a value.
+  pub valued_flag_count: usize,
+  /// The value given to the occurrence that decides each key, before it is split or transformed,
+  /// or `None` if that occurrence had no value. This is the last occurrence, or the first one for
+  /// a `keep_first()` key, and a key undone by a `negatable()` `--no-key` has no entry.
+  pub last_values: HashMap<String,Option<String>>,
+}
+
+impl ParseMeta {
+  /// Return the indexes of the arguments that held the values of `key`, or an empty slice if it
+  /// has no values, so `--n 1 -n2 --n=3` gives `[1,2,3]` for `n`.
+  pub fn indices_of(&self, key: &str) -> &[usize] {
+    self.value_indices.get(key).map(|v| v.as_slice()).unwrap_or(&[])
+  }
+  /// Read `key` as a feature that can be turned on with `--key`, turned off with an empty
+  /// `--key=`, or left to its default by not giving it. The occurrence recorded in `last_values`
+  /// decides, so `--key= --key` is `Tristate::Set`, `--key= --key` is `Tristate::Unset` for a
+  /// `keep_first()` key, and `--key --no-key` is `Tristate::Default` for a `negatable()` key.
+  /// Strict mode rejects `--key=` as suspicious, so use this with `parse_with_meta()`.
+  ///
+  /// ```
+  /// use argmap::Tristate;
+  /// let (_args,_argv,meta) = argmap::parse_with_meta(["--color", "--pager="].iter());
+  /// assert_eq![meta.tristate("color"), Tristate::Set];
+  /// assert_eq![meta.tristate("pager"), Tristate::Unset];
+  /// assert_eq![meta.tristate("cache"), Tristate::Default];
+  /// ```
+  pub fn tristate(&self, key: &str) -> Tristate {
+    match self.last_values.get(key) {
+      None => Tristate::Default,
+      Some(Some(v)) if v.is_empty() => Tristate::Unset,
+      Some(_) => Tristate::Set,
+    }
+  }
+}
+
+/// Whether a key was turned on, turned off, or not given, as returned by `ParseMeta::tristate()`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Tristate {
+  /// The last occurrence of the key had no value, as in `--key`, or a non-empty value.
+  Set,
+  /// The last occurrence of the key had an empty value, as in `--key=`.
+  Unset,
+  /// The key was not given.
+  Default,
+}
+
+/// Advice about input that was parsed but is probably a mistake, passed to the `on_warning()`
+/// callback. Indexes count from 0 over the input iterator.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Warning {
+  /// A `--` after an earlier `--`, which is kept as a positional argument.
+  RepeatedSeparator { index: usize },
+  /// A `boolean()` key given an explicit value, as in `--verbose=yes`, from `try_parse()` when
+  /// strict mode is on. The value is stored either way.
+  ValueOnBoolean { key: String, value: String },
+  /// A `value()` key that took an argument naming a known key as its value with `dash_values()`
+  /// on, as in `--name --verbose`, where the value for `name` was probably left out.
+  PossibleMissingValue { key: String, consumed: String },
+  /// A known option after a positional argument with `flags_before_positionals()` on.
+  FlagAfterPositional { key: String, index: usize },
+  /// A `negatable()` key given both as `--key` and as `--no-key`, where the last one wins.
+  /// `counted()` keys are not reported, since `--no-key` lowers their count instead.
+  Contradiction { key: String },
+}
+
+impl std::fmt::Display for Warning {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Self::RepeatedSeparator { index } => write![f, "repeated \"--\" at argument {}", index],
+      Self::ValueOnBoolean { key, value } => {
+        write![f, "boolean option {:?} given the value {:?}", key, value]
+      },
+      Self::PossibleMissingValue { key, consumed } => {
+        write![f, "option {:?} took {:?} as its value", key, consumed]
+      },
+      Self::FlagAfterPositional { key, index } => {
+        write![f, "option {:?} at argument {} comes after a positional argument", key, index]
+      },
+      Self::Contradiction { key } => {
+        write![f, "option {:?} given both as --{} and as --no-{}", key, key, key]
+      },
+    }
+  }
+}
+
+/// Errors returned by `try_parse()`. Most are only returned in strict mode.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum ParseError {
+  /// A `--key=value` assignment with an empty value or a value that starts with `=`,
+  /// as in `--key=` or `--key==value`.
+  SuspiciousValue { key: String, value: String },
+  /// A cluster of short options longer than the limit set with `max_cluster_len()`.
+  ClusterTooLong { token: String },
+  /// A single-dash argument like `-verbose` that names a known long key, so `--verbose` was
+  /// probably meant.
+  LikelyLongFlag { token: String },
+  /// A key registered with `value()` that was followed by another option or by the end of input
+  /// instead of by its value.
+  MissingValue { key: String },
+  /// A cluster of short options like `-xvz` that mixes known keys with a character that is not a
+  /// known key, when keys have been declared with `known()`.
+  UnknownCluster { token: String, bad_char: char },
+  /// A file named by a `file_values()` key that could not be read.
+  FileValue { key: String, path: String, kind: io::ErrorKind },
+  /// A value longer than the limit set with `max_value_len()`, with its length in bytes.
+  ValueTooLong { key: String, len: usize },
+  /// A known option after a positional argument with `flags_before_positionals()` on.
+  FlagAfterPositional { key: String },
+  /// An option that is not in the optstring of `getopt_mode()`, for which getopt(3) returns `'?'`.
+  InvalidOption { opt: char },
+  /// An option from `getopt_mode()` that requires an argument at the end of input. `code` is what
+  /// getopt(3) returns: `':'` if the optstring starts with `:`, or else `'?'`.
+  MissingArgument { opt: char, code: char },
+  /// A key given more than once that was not allowed with `repeatable()`.
+  Repeated { key: String },
+  /// A value that does not match the `value_pattern()`.
+  PatternMismatch { key: String, value: String },
+}
+
+impl std::fmt::Display for ParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Self::SuspiciousValue { key, value } => {
+        write![f, "suspicious value {:?} for key {:?}", value, key]
+      },
+      Self::ClusterTooLong { token } => {
+        write![f, "too many short options in cluster {:?}", token]
+      },
+      Self::LikelyLongFlag { token } => {
+        write![f, "{:?} looks like a long option missing a dash", token]
+      },
+      Self::MissingValue { key } => {
+        write![f, "missing value for {:?}", key]
+      },
+      Self::UnknownCluster { token, bad_char } => {
+        write![f, "unknown option {:?} in cluster {:?}", bad_char, token]
+      },
+      Self::FileValue { key, path, kind } => {
+        write![f, "could not read {:?} for {:?}: {}", path, key, kind]
+      },
+      Self::ValueTooLong { key, len } => {
+        write![f, "value for {:?} is too long ({} bytes)", key, len]
+      },
+      Self::FlagAfterPositional { key } => {
+        write![f, "option {:?} comes after a positional argument", key]
+      },
+      Self::InvalidOption { opt } => {
+        write![f, "invalid option -- '{}'", opt]
+      },
+      Self::MissingArgument { opt, .. } => {
+        write![f, "option requires an argument -- '{}'", opt]
+      },
+      Self::Repeated { key } => {
+        write![f, "option {:?} given more than once", key]
+      },
+      Self::PatternMismatch { key, value } => {
+        write![f, "value {:?} for {:?} does not match the pattern", value, key]
+      },
+    }
+  }
+}
+
+impl std::error::Error for ParseError {}
+
+fn check_value(key: &str, value: &str, separators: &[char]) -> Result<(),ParseError> {
+  if value.is_empty() || value.starts_with(separators) {
+    return Err(ParseError::SuspiciousValue { key: key.to_string(), value: value.to_string() });
+  }
+  Ok(())
+}
+
+#[allow(clippy::iter_nth_zero,clippy::bind_instead_of_map,clippy::manual_range_contains)]
 fn is_num(s: &str) -> bool {
   s.chars().nth(0).and_then(|c| Some('0' <= c && c <= '9')).unwrap_or(false)
 }
-fn short_break(s: &String) -> bool {
+fn interpolate(s: &str, unknown_empty: bool) -> String {
+  let mut out = String::new();
+  let mut rest = s;
+  while let Some(i) = rest.find('$') {
+    out.push_str(&rest[..i]);
+    rest = &rest[i+1..];
+    if let Some(r) = rest.strip_prefix('$') {
+      out.push('$');
+      rest = r;
+      continue;
+    }
+    let (name,len) = match rest.strip_prefix('{').and_then(|r| r.find('}')) {
+      Some(end) => (&rest[1..end+1], end+2),
+      None => {
+        let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+        (&rest[..end], end)
+      },
+    };
+    if !is_ident(name) {
+      out.push('$');
+      continue;
+    }
+    match std::env::var(name) {
+      Ok(v) => out.push_str(&v),
+      Err(_) if unknown_empty => {},
+      Err(_) => { out.push('$'); out.push_str(&rest[..len]); },
+    }
+    rest = &rest[len..];
+  }
+  out.push_str(rest);
+  out
+}
+fn correct_dashes(s: Cow<str>) -> Cow<str> {
+  match s.strip_prefix('\u{2014}').or_else(|| s.strip_prefix('\u{2013}')) {
+    Some(rest) => Cow::Owned(format!["--{}", rest]),
+    None => s,
+  }
+}
+fn is_escaped(s: &str) -> bool {
+  s.starts_with('\\') && s.trim_start_matches('\\').starts_with('-')
+}
+fn unquote(s: Cow<str>) -> Cow<str> {
+  let quoted = ['"','\''].iter().any(|q| s.len() >= 2 && s.starts_with(*q) && s.ends_with(*q));
+  if quoted {
+    let end = s.len() - 1;
+    cut(s, 1..end)
+  } else {
+    s
+  }
+}
+fn strip_index(k: &str) -> &str {
+  match k.strip_suffix(']').and_then(|k| k.rsplit_once('[')) {
+    Some((name,i)) if !name.is_empty() && i.chars().all(|c| c.is_ascii_digit()) => name,
+    _ => k,
+  }
+}
+fn is_ident(s: &str) -> bool {
+  let mut chars = s.chars();
+  chars.next().map(|c| c.is_alphabetic() || c == '_').unwrap_or(false)
+    && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+#[allow(clippy::bind_instead_of_map)]
+fn short_break(s: &str) -> bool {
   s.chars().next()
     .and_then(|c| Some(!c.is_alphabetic()))
     .unwrap_or(false)
 }
 
-fn set(argv: &mut Map, key: &String, value: &String) {
-  if let Some(values) = argv.get_mut(key) {
-    values.push(value.clone());
-  } else {
-    argv.insert(key.clone(), vec![value.clone()]);
+#[derive(Debug,Clone,Copy,Default)]
+struct Run<'b> {
+  strict: bool,
+  until_positional: bool,
+  raw: bool,
+  files: bool,
+  // ascending input indices before which a pending key ends, where parse_partial() took an option
+  breaks: &'b [usize],
+}
+
+fn set<'a>(out: &mut Vec<Token<Cow<'a,str>>>, key: Cow<'a,str>, value: Cow<'a,str>) {
+  out.push(Token::Opt { key, value: Some(value) });
+}
+fn set_bool<'a>(out: &mut Vec<Token<Cow<'a,str>>>, key: Cow<'a,str>) {
+  out.push(Token::Opt { key, value: None });
+}
+fn owned<T>(x: T) -> Cow<'static,str> where T: ToString {
+  Cow::Owned(x.to_string())
+}
+// a part of `s` that borrows from the input if `s` does
+fn part<'a,R>(s: &Cow<'a,str>, range: R) -> Cow<'a,str> where R: SliceIndex<str,Output=str> {
+  match s {
+    Cow::Borrowed(b) => Cow::Borrowed(&b[range]),
+    Cow::Owned(o) => Cow::Owned(o[range].to_string()),
+  }
+}
+// like `part()`, but reuse the buffer of an owned `s`
+fn cut<'a,R>(s: Cow<'a,str>, range: R) -> Cow<'a,str>
+where R: RangeBounds<usize>+SliceIndex<str,Output=str> {
+  match s {
+    Cow::Borrowed(b) => Cow::Borrowed(&b[range]),
+    Cow::Owned(mut o) => {
+      if let Bound::Excluded(end) = range.end_bound() {
+        o.truncate(*end);
+      }
+      if let Bound::Included(start) = range.start_bound() {
+        o.replace_range(..*start, "");
+      }
+      Cow::Owned(o)
+    },
   }
 }
-fn set_bool(argv: &mut Map, key: &String) {
-  if !argv.contains_key(key) {
-    argv.insert(key.clone(), vec![]);
+fn trim(s: Cow<str>) -> Cow<str> {
+  let start = s.len() - s.trim_start().len();
+  let end = s.trim_end().len().max(start);
+  cut(s, start..end)
+}
+fn split<'a>(s: &Cow<'a,str>, c: char) -> Vec<Cow<'a,str>> {
+  let mut parts = vec![];
+  let mut start = 0;
+  for (i,_) in s.match_indices(c) {
+    parts.push(part(s, start..i));
+    start = i + c.len_utf8();
   }
+  parts.push(part(s, start..));
+  parts
 }
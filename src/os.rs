@@ -0,0 +1,100 @@
+use crate::ArgMap;
+use std::collections::HashMap;
+use std::ffi::{OsStr,OsString};
+
+pub type OsMap = HashMap<String,Vec<OsString>>;
+
+impl ArgMap {
+  /// Parse arguments that may not be valid UTF-8, such as from `std::env::args_os()`. Positional
+  /// arguments and values are returned as `OsString` with their original bytes. Keys are decoded
+  /// lossily, so invalid bytes in a key become `U+FFFD`. On platforms other than Unix, values are
+  /// decoded lossily as well.
+  pub fn parse_os_vec(&self, input: Vec<OsString>) -> (Vec<OsString>,OsMap) {
+    let base = raw_base(&input);
+    let (args,argv) = self.parse(input.iter().map(|s| encode(s, base)));
+    let args = args.iter().map(|s| decode(s, base)).collect();
+    let argv = argv.into_iter().map(|(k,v)| {
+      (decode_key(&k, base), v.iter().map(|s| decode(s, base)).collect())
+    }).collect();
+    (args,argv)
+  }
+}
+
+/// Parse arguments that may not be valid UTF-8 with the default settings. See
+/// `ArgMap::parse_os_vec()`.
+pub fn parse_os_vec(input: Vec<OsString>) -> (Vec<OsString>,OsMap) {
+  ArgMap::new().parse_os_vec(input)
+}
+
+// bytes that are not valid UTF-8 are carried through the parser as characters from a block of 256
+// private use characters, one per byte. The block is picked so that none of the input uses it, so
+// only the invalid bytes are decoded back. If there are no invalid bytes, nothing is encoded, and
+// if the input uses every block, invalid bytes are replaced with U+FFFD.
+#[cfg(unix)]
+fn raw_base(input: &[OsString]) -> Option<u32> {
+  use std::collections::HashSet;
+  use std::os::unix::ffi::OsStrExt;
+  if input.iter().all(|s| s.to_str().is_some()) {
+    return None;
+  }
+  let used: HashSet<u32> = input.iter()
+    .flat_map(|s| s.as_bytes().utf8_chunks())
+    .flat_map(|chunk| chunk.valid().chars())
+    .map(|c| c as u32 & !0xff)
+    .collect();
+  (0xf00..=0x10ff).rev().map(|block| block << 8).find(|base| !used.contains(base))
+}
+
+#[cfg(not(unix))]
+fn raw_base(_input: &[OsString]) -> Option<u32> {
+  None
+}
+
+fn raw_byte(c: char, base: u32) -> Option<u8> {
+  (c as u32).checked_sub(base).filter(|b| *b < 0x100).map(|b| b as u8)
+}
+
+#[cfg(unix)]
+fn encode(s: &OsStr, base: Option<u32>) -> String {
+  use std::os::unix::ffi::OsStrExt;
+  let Some(base) = base else { return s.to_string_lossy().into_owned() };
+  let mut out = String::new();
+  for chunk in s.as_bytes().utf8_chunks() {
+    out.push_str(chunk.valid());
+    for b in chunk.invalid() {
+      out.extend(char::from_u32(base + *b as u32));
+    }
+  }
+  out
+}
+
+#[cfg(unix)]
+fn decode(s: &str, base: Option<u32>) -> OsString {
+  use std::os::unix::ffi::OsStringExt;
+  let Some(base) = base else { return OsString::from(s) };
+  let mut out = vec![];
+  for c in s.chars() {
+    match raw_byte(c, base) {
+      Some(b) => out.push(b),
+      None => out.extend_from_slice(c.encode_utf8(&mut [0;4]).as_bytes()),
+    }
+  }
+  OsString::from_vec(out)
+}
+
+#[cfg(not(unix))]
+fn encode(s: &OsStr, _base: Option<u32>) -> String {
+  s.to_string_lossy().into_owned()
+}
+
+#[cfg(not(unix))]
+fn decode(s: &str, _base: Option<u32>) -> OsString {
+  OsString::from(s)
+}
+
+fn decode_key(s: &str, base: Option<u32>) -> String {
+  match base {
+    Some(base) => s.chars().map(|c| raw_byte(c, base).map(|_| '\u{fffd}').unwrap_or(c)).collect(),
+    None => s.to_string(),
+  }
+}
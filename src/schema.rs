@@ -0,0 +1,190 @@
+use crate::{ArgMap,List,Map};
+use std::collections::HashMap;
+
+/// The type of value a `Schema` flag accepts.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Kind {
+  /// A boolean flag that takes no value.
+  Bool,
+  /// Any string value.
+  Str,
+  /// A value that parses as `i64`.
+  I64,
+  /// A value that parses as `u64`.
+  U64,
+  /// A value that parses as `u16`, such as a port number.
+  U16,
+  /// A value that parses as `f64`.
+  F64,
+}
+
+impl Kind {
+  fn accepts(&self, value: &str) -> bool {
+    match self {
+      Self::Bool => false,
+      Self::Str => true,
+      Self::I64 => value.parse::<i64>().is_ok(),
+      Self::U64 => value.parse::<u64>().is_ok(),
+      Self::U16 => value.parse::<u16>().is_ok(),
+      Self::F64 => value.parse::<f64>().is_ok(),
+    }
+  }
+}
+
+/// A set of expected keys with their types, for validating parsed arguments in one step.
+///
+/// ```
+/// use argmap::{Schema,Kind};
+/// let schema = Schema::new()
+///   .flag("verbose", Kind::Bool)
+///   .flag("port", Kind::U16)
+///   .required("input");
+/// let parsed = schema.parse(["--port","8080","--input","x.txt","-"].iter()).unwrap();
+/// assert_eq![parsed.get::<u16>("port"), Some(8080)];
+/// assert![!parsed.flag("verbose")];
+/// assert_eq![parsed.args(), &vec!["-".to_string()]];
+/// ```
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub struct Schema {
+  pub flags: Vec<(String,Kind)>,
+  pub required: Vec<String>,
+}
+
+impl Schema {
+  /// Create an empty schema.
+  pub fn new() -> Self {
+    Self::default()
+  }
+  /// Accept `key` with values of type `kind`, replacing any kind given for it before.
+  pub fn flag<T>(mut self, key: T, kind: Kind) -> Self where T: ToString {
+    let key = key.to_string();
+    match self.flags.iter_mut().find(|(k,_)| *k == key) {
+      Some(flag) => flag.1 = kind,
+      None => self.flags.push((key, kind)),
+    }
+    self
+  }
+  /// Require `key` to be given. A key without a kind from `flag()` is accepted as `Kind::Str`.
+  pub fn required<T>(mut self, key: T) -> Self where T: ToString {
+    let key = key.to_string();
+    if !self.flags.iter().any(|(k,_)| *k == key) {
+      self.flags.push((key.clone(), Kind::Str));
+    }
+    self.required.push(key);
+    self
+  }
+  /// Parse an iterator of string arguments and check them against the schema, returning every
+  /// violation instead of stopping at the first one.
+  pub fn parse<T>(&self, input: impl Iterator<Item=T>) -> Result<Parsed,Vec<SchemaError>>
+  where T: ToString {
+    let kinds: HashMap<&str,Kind> = self.flags.iter().map(|(k,kind)| (k.as_str(), *kind)).collect();
+    let mut argmap = ArgMap::new();
+    for (key,kind) in self.flags.iter() {
+      argmap = match kind {
+        Kind::Bool => argmap.boolean(key),
+        _ => argmap.value(key),
+      };
+    }
+    let (args,argv) = argmap.parse(input);
+    let mut errors = vec![];
+    let mut keys: Vec<&String> = argv.keys().collect();
+    keys.sort();
+    for key in keys {
+      let values = &argv[key];
+      match kinds.get(key.as_str()) {
+        None => errors.push(SchemaError::UnknownKey { key: key.clone() }),
+        Some(Kind::Bool) => {
+          if let Some(value) = values.first() {
+            errors.push(SchemaError::InvalidValue {
+              key: key.clone(),
+              value: value.clone(),
+              kind: Kind::Bool,
+            });
+          }
+        },
+        Some(kind) => {
+          if values.is_empty() {
+            errors.push(SchemaError::MissingValue { key: key.clone() });
+          }
+          for value in values.iter().filter(|v| !kind.accepts(v)) {
+            errors.push(SchemaError::InvalidValue {
+              key: key.clone(),
+              value: value.clone(),
+              kind: *kind,
+            });
+          }
+        },
+      }
+    }
+    for key in self.required.iter().filter(|k| !argv.contains_key(*k)) {
+      errors.push(SchemaError::MissingRequired { key: key.clone() });
+    }
+    if errors.is_empty() {
+      Ok(Parsed { args, argv })
+    } else {
+      Err(errors)
+    }
+  }
+}
+
+/// Arguments that passed validation by `Schema::parse()`.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Parsed {
+  args: List,
+  argv: Map,
+}
+
+impl Parsed {
+  /// Return the positional arguments.
+  pub fn args(&self) -> &List {
+    &self.args
+  }
+  /// Return the map of keys to values.
+  pub fn argv(&self) -> &Map {
+    &self.argv
+  }
+  /// Return whether `key` was given.
+  pub fn flag(&self, key: &str) -> bool {
+    self.argv.contains_key(key)
+  }
+  /// Return the last value for `key` as a string.
+  pub fn get_str(&self, key: &str) -> Option<&str> {
+    self.argv.get(key).and_then(|v| v.last()).map(|s| s.as_str())
+  }
+  /// Return the last value for `key` parsed as `T`, which should match the key's `Kind`.
+  pub fn get<T>(&self, key: &str) -> Option<T> where T: std::str::FromStr {
+    self.get_str(key).and_then(|s| s.parse().ok())
+  }
+  /// Return the positional arguments and the map of keys to values.
+  pub fn into_inner(self) -> (List,Map) {
+    (self.args,self.argv)
+  }
+}
+
+/// A violation found by `Schema::parse()`.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum SchemaError {
+  /// A key that the schema does not list.
+  UnknownKey { key: String },
+  /// A required key that was not given.
+  MissingRequired { key: String },
+  /// A key that takes a value but was given without one.
+  MissingValue { key: String },
+  /// A value that does not parse as the key's `Kind`, or any value for a `Kind::Bool` key.
+  InvalidValue { key: String, value: String, kind: Kind },
+}
+
+impl std::fmt::Display for SchemaError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Self::UnknownKey { key } => write![f, "unknown key {:?}", key],
+      Self::MissingRequired { key } => write![f, "missing required key {:?}", key],
+      Self::MissingValue { key } => write![f, "missing value for {:?}", key],
+      Self::InvalidValue { key, value, kind } => {
+        write![f, "invalid value {:?} for {:?}, expected {:?}", value, key, kind]
+      },
+    }
+  }
+}
+
+impl std::error::Error for SchemaError {}
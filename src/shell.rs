@@ -0,0 +1,97 @@
+/// Split a string into arguments the way a POSIX shell would, without any expansion.
+///
+/// Arguments are separated by unquoted whitespace. Single quotes preserve their contents literally,
+/// double quotes preserve their contents except for `\"` and `\\` escapes, and a backslash outside
+/// of quotes escapes the next character. An unterminated quote extends to the end of the input.
+///
+/// ```
+/// assert_eq![
+///   argmap::tokenize(r#"-x 'a b' --msg="hi \"you\"" c\ d"#),
+///   vec!["-x", "a b", r#"--msg=hi "you""#, "c d"]
+/// ];
+/// ```
+pub fn tokenize(s: &str) -> Vec<String> {
+  split(s).0
+}
+
+/// Split a string into arguments like `tokenize()`, but return an error if the input ends inside
+/// of a quote, so that a caller reading lines interactively can ask for more input.
+///
+/// ```
+/// use argmap::TokenizeError;
+/// assert_eq![argmap::try_tokenize("a 'b c'"), Ok(vec!["a".to_string(), "b c".to_string()])];
+/// assert_eq![argmap::try_tokenize("a 'b c"), Err(TokenizeError::Unterminated {
+///   partial: "b c".to_string(),
+/// })];
+/// ```
+pub fn try_tokenize(s: &str) -> Result<Vec<String>,TokenizeError> {
+  match split(s) {
+    (mut out, true) => Err(TokenizeError::Unterminated { partial: out.pop().unwrap_or_default() }),
+    (out, false) => Ok(out),
+  }
+}
+
+/// Error returned by `try_tokenize()` and `ArgMap::parse_str()`.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum TokenizeError {
+  /// The input ended inside of a quote. `partial` is the unfinished argument read so far.
+  Unterminated { partial: String },
+}
+
+impl std::fmt::Display for TokenizeError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      Self::Unterminated { partial } => write![f, "unterminated quote in {:?}", partial],
+    }
+  }
+}
+
+impl std::error::Error for TokenizeError {}
+
+fn split(s: &str) -> (Vec<String>,bool) {
+  let mut out = vec![];
+  let mut open = false;
+  let mut cur: Option<String> = None;
+  let mut chars = s.chars();
+  while let Some(c) = chars.next() {
+    match c {
+      '\'' => {
+        let word = cur.get_or_insert_with(String::new);
+        open = true;
+        for c in chars.by_ref() {
+          if c == '\'' { open = false; break }
+          word.push(c);
+        }
+      },
+      '"' => {
+        let word = cur.get_or_insert_with(String::new);
+        open = true;
+        while let Some(c) = chars.next() {
+          match c {
+            '"' => { open = false; break },
+            '\\' => match chars.next() {
+              Some(e) if e == '"' || e == '\\' => word.push(e),
+              Some(e) => { word.push('\\'); word.push(e) },
+              None => word.push('\\'),
+            },
+            c => word.push(c),
+          }
+        }
+      },
+      '\\' => {
+        let word = cur.get_or_insert_with(String::new);
+        word.push(chars.next().unwrap_or('\\'));
+      },
+      c if c.is_whitespace() => {
+        if let Some(word) = cur.take() {
+          out.push(word);
+        }
+      },
+      c => cur.get_or_insert_with(String::new).push(c),
+    }
+  }
+  if let Some(word) = cur {
+    out.push(word);
+  }
+  (out,open)
+}
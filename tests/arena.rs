@@ -0,0 +1,20 @@
+#![cfg(feature="bumpalo")]
+use pretty_assertions::assert_eq;
+use bumpalo::Bump;
+
+#[test] fn parse_in_arena() {
+  let input = ["-x", "5", "--flag", "one", "-vy=z", "-ccc", "--", "-q"];
+  let parser = argmap::new().counted("c");
+  let arena = Bump::new();
+  let (args,argv) = parser.parse_in(input.iter(), &arena);
+  let (owned_args,owned_argv) = parser.parse(input.iter());
+  assert_eq![args, owned_args];
+  assert_eq![argv.len(), owned_argv.len()];
+  for (k,v) in owned_argv.iter() {
+    assert_eq![argv.get(k.as_str()), Some(&v.iter().map(|s| s.as_str()).collect())];
+  }
+  assert![arena.allocated_bytes() > 0];
+  let in_input = |s: &str| input.iter().any(|x| x.as_bytes().as_ptr_range().contains(&s.as_ptr()));
+  assert![!args.iter().any(|s| in_input(s))];
+  assert![!argv.iter().any(|(k,v)| in_input(k) || v.iter().any(|s| in_input(s)))];
+}
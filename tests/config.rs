@@ -0,0 +1,22 @@
+#[test] fn config_builder_paths_eq() {
+  let a = argmap::new()
+    .booleans(&["a","b"])
+    .values(&["o"])
+    .alias("verbose","v");
+  let b = argmap::new()
+    .boolean("b")
+    .boolean_char('a')
+    .value("o")
+    .alias("verbose","v");
+  assert![a == b];
+  assert![a != argmap::new().booleans(&["a","b"])];
+}
+
+#[test] fn config_map_value_closures_ne() {
+  let a = argmap::new().map_value("x", Box::new(|s| s.to_uppercase()));
+  let b = argmap::new().map_value("x", Box::new(|s| s.trim().to_string()));
+  assert![a != b];
+  assert![a != argmap::new().map_value("y", Box::new(|s| s))];
+  let f = || -> Box<dyn Fn(String) -> String> { Box::new(|s| s) };
+  assert![argmap::new().map_value("x", f()) != argmap::new().map_value("x", f())];
+}
@@ -0,0 +1,53 @@
+use pretty_assertions::assert_eq;
+use argmap::{ArgMapExt,MultipleValuesError,RequireError};
+
+#[test] fn require_value() {
+  let (_args,argv) = argmap::parse(["--empty","--port","80","--port","8080"].iter());
+  assert_eq![argv.require_value("missing"), Err(RequireError::Absent)];
+  assert_eq![argv.require_value("empty"), Err(RequireError::EmptyValue)];
+  assert_eq![argv.require_value("port").map(|s| s.as_str()), Ok("8080")];
+}
+
+#[test] fn get_parsed_all() {
+  let (_args,argv) = argmap::parse(["--id","1","--id","2","--id","notanum","--id=-4"].iter());
+  let ids = argv.get_parsed_all::<i32>("id");
+  assert_eq![ids.len(), 4];
+  assert_eq![ids[0], Ok(1)];
+  assert_eq![ids[1], Ok(2)];
+  assert![ids[2].is_err()];
+  assert_eq![ids[3], Ok(-4)];
+  assert![argv.get_parsed_all::<i32>("missing").is_empty()];
+}
+
+#[test] fn get_single() {
+  let (_args,argv) = argmap::parse(["--one","1","--two","2","--two","3","--bool"].iter());
+  assert_eq![argv.get_single("missing"), Ok(None)];
+  assert_eq![argv.get_single("bool"), Ok(None)];
+  assert_eq![argv.get_single("one").map(|v| v.map(|s| s.as_str())), Ok(Some("1"))];
+  assert_eq![
+    argv.get_single("two"),
+    Err(MultipleValuesError { key: "two".to_string(), count: 2 })
+  ];
+}
+
+#[test] fn get_or() {
+  let (_args,argv) = argmap::parse(["--infile","a","--infile","b","-q"].iter());
+  assert_eq![argv.get_or("infile", "-"), "b"];
+  assert_eq![argv.get_or("q", "-"), "-"];
+  assert_eq![argv.get_or("missing", "-"), "-"];
+  let default = vec!["-".to_string()];
+  assert_eq![argv.get_all_or("infile", &default), ["a","b"]];
+  assert_eq![argv.get_all_or("q", &default), ["-"]];
+  assert_eq![argv.get_all_or("missing", &[]), Vec::<String>::new().as_slice()];
+}
+
+#[test] fn present_set() {
+  let (_args,argv) = argmap::parse(["-a","--c=1","-e"].iter());
+  assert_eq![argv.present_set(&["a","b","c","d","e"]), 0b10101];
+  assert_eq![argv.present_set(&["x","a"]), 0b10];
+  assert_eq![argv.present_set(&[]), 0];
+  let mut keys: Vec<String> = (0..69).map(|i| i.to_string()).collect();
+  keys.push("a".to_string());
+  let keys: Vec<&str> = keys.iter().map(|k| k.as_str()).collect();
+  assert_eq![argv.present_set(&keys), 0];
+}
@@ -0,0 +1,33 @@
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+use argmap::ParseError;
+
+#[test] fn file_values_read() {
+  let path = std::env::temp_dir().join(format!["argmap-file-values-{}.txt", std::process::id()]);
+  std::fs::write(&path, "hello\nworld\n").unwrap();
+  let arg = format!["@{}", path.display()];
+  let parser = argmap::new().file_values(&["data"]);
+  let res = parser.try_parse(["--data", arg.as_str(), "--data=@@literal", "--other", &arg].iter());
+  assert_eq![res, Ok((vec![],hash([
+    ("data",vec!["hello\nworld\n","@literal"]),
+    ("other",vec![arg.as_str()]),
+  ].iter())))];
+  let res = parser.trim_file_values(true).try_parse(["--data", &arg].iter());
+  assert_eq![res, Ok((vec![],hash([("data",vec!["hello\nworld"])].iter())))];
+  let (_args,argv) = argmap::new().file_values(&["data"]).parse(["--data", &arg].iter());
+  assert_eq![argv, hash([("data",vec![arg.as_str()])].iter())];
+  std::fs::remove_file(&path).unwrap();
+}
+
+#[test] fn file_values_missing() {
+  let res = argmap::new().file_values(&["d"]).try_parse(["-d", "@/nonexistent/argmap"].iter());
+  assert_eq![res, Err(ParseError::FileValue {
+    key: "d".to_string(),
+    path: "/nonexistent/argmap".to_string(),
+    kind: std::io::ErrorKind::NotFound,
+  })];
+}
+
+fn hash<'a>(i: impl Iterator<Item=&'a (&'a str,Vec<&'a str>)>) -> HashMap<String,Vec<String>> {
+  i.map(|(k,v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect())).collect()
+}
@@ -0,0 +1,66 @@
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+use argmap::ParseError;
+
+#[test] fn getopt_example() {
+  // the example program from the getopt(3) man page
+  let parser = argmap::new().getopt_mode("nt:");
+  let (args,argv) = parser.parse(["-n", "-t", "5", "file"].iter());
+  assert_eq![args, vec!["file"]];
+  assert_eq![argv, hash([("n",vec![]),("t",vec!["5"])].iter())];
+  let (args,argv) = parser.parse(["-nt5", "a", "-t", "-n", "b"].iter());
+  assert_eq![args, vec!["a","b"]];
+  assert_eq![argv, hash([("n",vec![]),("t",vec!["5","-n"])].iter())];
+  let (args,argv) = parser.parse(["-t", "--", "-n", "--", "-t"].iter());
+  assert_eq![args, vec!["-t"]];
+  assert_eq![argv, hash([("n",vec![]),("t",vec!["--"])].iter())];
+}
+
+#[test] fn getopt_optional_argument() {
+  let parser = argmap::new().getopt_mode("ac::");
+  let (args,argv) = parser.parse(["-cfoo", "-c", "bar", "-ac"].iter());
+  assert_eq![args, vec!["bar"]];
+  assert_eq![argv, hash([("a",vec![]),("c",vec!["foo"])].iter())];
+}
+
+#[test] fn getopt_stop_at_positional() {
+  let (args,argv) = argmap::new().getopt_mode("+n").parse(["-n", "file", "-n"].iter());
+  assert_eq![args, vec!["file","-n"]];
+  assert_eq![argv, hash([("n",vec![])].iter())];
+  let (args,argv) = argmap::new().getopt_mode("-n").parse(["-n", "file", "-n", "x"].iter());
+  assert_eq![args, vec!["file","x"]];
+  assert_eq![argv, hash([("n",vec![])].iter())];
+}
+
+#[test] fn getopt_errors() {
+  let parser = argmap::new().getopt_mode("nt:").strict(true);
+  assert_eq![parser.try_parse(["-nx"].iter()), Err(ParseError::InvalidOption { opt: 'x' })];
+  assert_eq![parser.try_parse(["--long"].iter()), Err(ParseError::InvalidOption { opt: '-' })];
+  assert_eq![parser.try_parse(["-n:"].iter()), Err(ParseError::InvalidOption { opt: ':' })];
+  assert_eq![
+    parser.try_parse(["-n", "-t"].iter()),
+    Err(ParseError::MissingArgument { opt: 't', code: '?' })
+  ];
+  let parser = argmap::new().getopt_mode("+:nt:").strict(true);
+  assert_eq![
+    parser.try_parse(["-nt"].iter()),
+    Err(ParseError::MissingArgument { opt: 't', code: ':' })
+  ];
+  assert_eq![
+    ParseError::MissingArgument { opt: 't', code: ':' }.to_string(),
+    "option requires an argument -- 't'"
+  ];
+  let (args,argv) = argmap::new().getopt_mode("n").parse(["-xn", "--ab"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([
+    ("x",vec![]),
+    ("n",vec![]),
+    ("-",vec![]),
+    ("a",vec![]),
+    ("b",vec![]),
+  ].iter())];
+}
+
+fn hash<'a>(i: impl Iterator<Item=&'a (&'a str,Vec<&'a str>)>) -> HashMap<String,Vec<String>> {
+  i.map(|(k,v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect())).collect()
+}
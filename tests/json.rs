@@ -0,0 +1,28 @@
+#![cfg(feature="json")]
+
+#[test] fn json_shape() {
+  let (args,argv) = argmap::new().boolean("v").parse([
+    "-v", "--msg", "say \"hi\"\n", "one", "-x", "1", "-x", "2", "two"
+  ].iter());
+  assert_eq![
+    argmap::to_json(&args, &argv),
+    r#"{"_":["one","two"],"flags":{"msg":["say \"hi\"\n"],"v":[],"x":["1","2"]}}"#
+  ];
+}
+
+#[test] fn json_empty() {
+  let (args,argv) = argmap::parse(Vec::<String>::new().iter());
+  assert_eq![argmap::to_json(&args, &argv), r#"{"_":[],"flags":{}}"#];
+}
+
+#[test] fn json_bool_style() {
+  let (args,argv) = argmap::new().boolean("v").parse(["-v", "--n=1"].iter());
+  assert_eq![
+    argmap::to_json_with(&args, &argv, argmap::BoolStyle::True),
+    r#"{"_":[],"flags":{"n":["1"],"v":true}}"#
+  ];
+  assert_eq![
+    argmap::to_json_with(&args, &argv, argmap::BoolStyle::Bare),
+    r#"{"_":[],"flags":{"n":["1"],"v":[]}}"#
+  ];
+}
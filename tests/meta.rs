@@ -0,0 +1,124 @@
+use pretty_assertions::assert_eq;
+
+#[test] fn meta_passthrough_from_dashdash() {
+  let (args,_argv,meta) = argmap::parse_with_meta([
+    "tool", "-x", "5", "a", "--", "--y", "--"
+  ].iter());
+  assert_eq![args, vec!["tool","a","--y","--"]];
+  assert_eq![meta.passthrough_from, Some(4)];
+  let (_args,_argv,meta) = argmap::parse_with_meta(["tool","-x"].iter());
+  assert_eq![meta.passthrough_from, None];
+}
+
+#[test] fn meta_passthrough_from_raw_after() {
+  let (args,_argv,meta) = argmap::new().raw_after("run").parse_with_meta([
+    "tool", "run", "--fast"
+  ].iter());
+  assert_eq![args, vec!["tool","run","--fast"]];
+  assert_eq![meta.passthrough_from, Some(1)];
+}
+
+#[test] fn meta_occurrences() {
+  let (_args,argv,meta) = argmap::new().boolean("v").greedy("msg").parse_with_meta([
+    "-v", "-v", "-vv", "--msg", "a", "b", "--n=1", "--n", "2", "x"
+  ].iter());
+  assert_eq![argv.get("v"), Some(&vec![])];
+  assert_eq![meta.occurrences.get("v"), Some(&4)];
+  assert_eq![meta.occurrences.get("msg"), Some(&1)];
+  assert_eq![meta.occurrences.get("n"), Some(&2)];
+  assert_eq![meta.occurrences.get("x"), None];
+}
+
+#[test] fn meta_values_from_positions() {
+  let (args,_argv,meta) = argmap::new().boolean("v").greedy("msg").parse_with_meta([
+    "--file", "foo", "bar", "-v", "baz", "-n", "-5", "-xy", "--=z", "--msg", "a", "b", "--k=1"
+  ].iter());
+  assert_eq![args, vec!["bar","baz"]];
+  assert_eq![meta.values_from_positions, vec![1,6,8,10,11]];
+}
+
+#[test] fn meta_help_and_version_requested() {
+  let parser = argmap::new().help_flags(&["h","help"]).version_flags(&["version"]);
+  let (args,_argv,meta) = parser.parse_with_meta(["-h", "file"].iter());
+  assert_eq![args, vec!["file"]];
+  assert![meta.help_requested];
+  assert![!meta.version_requested];
+  let (_args,_argv,meta) = parser.parse_with_meta(["--version"].iter());
+  assert![!meta.help_requested];
+  assert![meta.version_requested];
+  let (_args,_argv,meta) = parser.parse_with_meta(["-x", "--", "--help"].iter());
+  assert![!meta.help_requested];
+}
+
+#[test] fn meta_alias_hits() {
+  let parser = argmap::new().boolean("bytes").alias("c","bytes").alias("w","words");
+  let (_args,argv,meta) = parser.parse_with_meta(["-c", "--bytes", "-wc", "--lines"].iter());
+  assert_eq![argv.len(), 3];
+  assert_eq![meta.alias_hits, vec![
+    ("c".to_string(),"bytes".to_string()),
+    ("w".to_string(),"words".to_string()),
+    ("c".to_string(),"bytes".to_string()),
+  ]];
+}
+
+#[test] fn meta_indices_of() {
+  let parser = argmap::new().boolean("v").greedy("msg");
+  let (_args,argv,meta) = parser.parse_with_meta([
+    "--n", "1", "-v", "-n2", "pos", "--n=3", "-xvn", "4", "--msg", "a", "b",
+  ].iter());
+  assert_eq![argv.get("n").map(|v| v.len()), Some(4)];
+  assert_eq![meta.indices_of("n"), &[1,3,5,7]];
+  assert_eq![meta.indices_of("msg"), &[9,10]];
+  assert_eq![meta.indices_of("v"), &[] as &[usize]];
+  assert_eq![meta.indices_of("missing"), &[] as &[usize]];
+}
+
+#[test] fn meta_separator_positional_index() {
+  let (args,_argv,meta) = argmap::parse_with_meta(["a", "-x", "b", "--", "c", "--", "d"].iter());
+  assert_eq![args, vec!["a","c","--","d"]];
+  assert_eq![meta.separator_positional_index, Some(1)];
+  let (args,_argv,meta) = argmap::parse_with_meta(["a", "b", "--"].iter());
+  assert_eq![args, vec!["a","b"]];
+  assert_eq![meta.separator_positional_index, Some(2)];
+  let (_args,_argv,meta) = argmap::parse_with_meta(["a", "b"].iter());
+  assert_eq![meta.separator_positional_index, None];
+}
+
+#[test] fn meta_counts() {
+  let parser = argmap::new().boolean("v").greedy("msg");
+  let (args,_argv,meta) = parser.parse_with_meta([
+    "a", "-xvf", "file", "--n=1", "-v", "--msg", "hi", "there", "-q", "--", "-z", "b",
+  ].iter());
+  assert_eq![args, vec!["a","-z","b"]];
+  assert_eq![meta.flag_count, 7];
+  assert_eq![meta.positional_count, 3];
+  assert_eq![meta.valued_flag_count, 3];
+  let (_args,_argv,meta) = argmap::parse_with_meta(Vec::<String>::new().iter());
+  assert_eq![(meta.flag_count,meta.positional_count,meta.valued_flag_count), (0,0,0)];
+}
+
+#[test] fn meta_tristate() {
+  use argmap::Tristate;
+  let input = ["--a", "--b=", "--c=", "--c", "--d", "--d=", "--e=on", "--g=", "--g=x"];
+  let (_args,argv,meta) = argmap::new().boolean("a").parse_with_meta(input.iter());
+  assert_eq![meta.tristate("a"), Tristate::Set];
+  assert_eq![meta.tristate("b"), Tristate::Unset];
+  assert_eq![meta.tristate("c"), Tristate::Set];
+  assert_eq![meta.tristate("d"), Tristate::Unset];
+  assert_eq![meta.tristate("e"), Tristate::Set];
+  assert_eq![meta.tristate("f"), Tristate::Default];
+  assert_eq![meta.tristate("g"), Tristate::Set];
+  assert_eq![argv.get("c"), Some(&vec!["".to_string()])];
+  let parser = argmap::new().negatable("color").negatable("v").counted("v").keep_first(&["pager"]);
+  let input = ["--color", "--no-color", "--pager=", "--pager", "-v", "--no-v"];
+  let (_args,argv,meta) = parser.parse_with_meta(input.iter());
+  assert_eq![argv.get("color"), None];
+  assert_eq![meta.tristate("color"), Tristate::Default];
+  assert_eq![argv.get("pager"), Some(&vec!["".to_string()])];
+  assert_eq![meta.tristate("pager"), Tristate::Unset];
+  assert_eq![argv.get("v"), None];
+  assert_eq![meta.tristate("v"), Tristate::Default];
+  let (_args,_argv,meta) = parser.parse_with_meta(["--no-color", "--color=", "-vv"].iter());
+  assert_eq![meta.tristate("color"), Tristate::Unset];
+  assert_eq![meta.tristate("v"), Tristate::Set];
+}
@@ -0,0 +1,67 @@
+use argmap::ArgMap;
+use proptest::prelude::*;
+
+// Generate token lists from a small alphabet that is heavy on the characters the parser treats
+// specially, including multibyte characters, and check that parsing never panics.
+const PIECES: &[&str] = &[
+  "-", "--", "=", "a", "b", "x", "5", "é", "—", "–", "\u{1F600}", "\\", "[", "]", "0", "+", ":",
+  " ", "@", "'", "\"", "no-", ",", ";", "$", "{", "}",
+];
+
+fn tokens() -> impl Strategy<Value=Vec<String>> {
+  let token = prop::collection::vec(prop::sample::select(PIECES), 0..6).prop_map(|t| t.concat());
+  prop::collection::vec(token, 0..8)
+}
+
+fn parsers() -> Vec<ArgMap> {
+  vec![
+    argmap::new(),
+    argmap::new().strict(true),
+    argmap::new().booleans(&["a","x"]).values(&["b","5"]).known(&["a","b"]).strict(true),
+    argmap::new().no_clustering(true).long_case_insensitive(true).normalize_dashes(true),
+    argmap::new().max_cluster_len(2).max_value_len(1).trim_tokens(true),
+    argmap::new().autocorrect_dashes(true).backslash_escape(true).promote_kv_positionals(true),
+    argmap::new().greedy("a").counted("x").negatable("b").terminating_flags(&["5"]),
+    argmap::new().kv_separators(&['=',':','é']).array_syntax(true).strip_quotes(true),
+    argmap::new().kv_separators(&['-','\\']).alias("x","a").help_flags(&["a"]),
+    argmap::new().value("a").dash_values(true).implies("x", &["é=1","b"]).split_on("a", ','),
+    argmap::new().interpolate_env(true).positional_names(&["p"]).rest_key("r"),
+    argmap::new().getopt_mode("+:ab:é::").strict(true),
+    argmap::new().short_flag_predicate(Box::new(|s| s.len() > 2))
+      .short_break_fn(Box::new(|s| s == "é")),
+  ]
+}
+
+proptest! {
+  #![proptest_config(ProptestConfig::with_cases(5_000))]
+
+  #[test] fn parse_never_panics(input in tokens(), p in 0..parsers().len()) {
+    let parser = &parsers()[p];
+    let _ = parser.parse(input.iter());
+    let _ = parser.try_parse(input.iter());
+    let _ = parser.parse_with_meta(input.iter());
+    let _ = parser.parse_partial(input.iter());
+    let _ = argmap::parse_str(&input.join(" "));
+    for token in input.iter() {
+      let _ = parser.token_kind(token, None);
+      let _ = parser.token_kind(token, Some("a"));
+    }
+  }
+
+  #[test] fn parse_any_strings_never_panics(input in prop::collection::vec(any::<String>(), 0..8)) {
+    for parser in parsers().iter() {
+      let _ = parser.parse(input.iter());
+      let _ = parser.try_parse(input.iter());
+      let _ = parser.parse_with_meta(input.iter());
+    }
+  }
+
+  #[test] fn parse_bytes_never_panics(bytes in prop::collection::vec(prop_oneof![
+    1 => Just(b'-'),
+    1 => Just(0u8),
+    2 => any::<u8>(),
+  ], 0..24)) {
+    let _ = argmap::parse_bytes(&bytes);
+    let _ = argmap::try_parse_bytes(&bytes);
+  }
+}
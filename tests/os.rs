@@ -0,0 +1,40 @@
+#![cfg(unix)]
+use pretty_assertions::assert_eq;
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStringExt;
+
+#[test] fn os_vec_non_utf8_value() {
+  let raw = OsString::from_vec(vec![b'a', 0xff, b'b', 0xfe]);
+  let mut eq = b"--name=".to_vec();
+  eq.extend_from_slice(&[0xc3, 0x28]);
+  let input = vec![
+    OsString::from("-f"), raw.clone(),
+    OsString::from_vec(eq),
+    OsString::from("--x"), OsString::from("y"),
+    raw.clone(),
+  ];
+  let (args,argv) = argmap::parse_os_vec(input);
+  assert_eq![args, vec![raw.clone()]];
+  assert_eq![argv.get("f"), Some(&vec![raw])];
+  assert_eq![argv.get("name"), Some(&vec![OsString::from_vec(vec![0xc3, 0x28])])];
+  assert_eq![argv.get("x"), Some(&vec![OsString::from("y")])];
+}
+
+#[test] fn os_vec_non_utf8_key() {
+  let key = OsString::from_vec(vec![b'-', b'-', b'k', 0xff]);
+  let (_args,argv) = argmap::parse_os_vec(vec![key, OsString::from("v")]);
+  assert_eq![argv.get("k\u{fffd}"), Some(&vec![OsString::from("v")])];
+}
+
+#[test] fn os_vec_private_use_chars() {
+  let pua = OsString::from("\u{10ff41}");
+  let (args,_argv) = argmap::parse_os_vec(vec![pua.clone()]);
+  assert_eq![args, vec![pua.clone()]];
+  let raw = OsString::from_vec(vec![b'a', 0xff]);
+  let mut key = "--k\u{10ff41}\u{10ffff}".as_bytes().to_vec();
+  key.push(0xfe);
+  let input = vec![OsString::from_vec(key), raw.clone(), pua.clone(), raw.clone()];
+  let (args,argv) = argmap::parse_os_vec(input);
+  assert_eq![args, vec![pua, raw.clone()]];
+  assert_eq![argv.get("k\u{10ff41}\u{10ffff}\u{fffd}"), Some(&vec![raw])];
+}
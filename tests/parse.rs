@@ -1,5 +1,5 @@
 use pretty_assertions::assert_eq;
-use std::collections::HashMap;
+use std::collections::{HashMap,HashSet};
 
 #[test] fn parse_junk0() {
   let (args,argv) = argmap::parse([
@@ -148,6 +148,53 @@ use std::collections::HashMap;
   ].iter())];
 }
 
+#[test] fn parse_boolean_cluster_final() {
+  let (args,argv) = argmap::new().boolean("q").parse([
+    "-xq", "foo", "--y", "5", "--y", "--z"
+  ].iter());
+  assert_eq![args, vec!["foo"]];
+  assert_eq![argv, hash([
+    ("x",vec![]),
+    ("q",vec![]),
+    ("y",vec!["5"]),
+    ("z",vec![]),
+  ].iter())];
+}
+
+#[test] fn parse_boolean_equals_value() {
+  let (args,argv) = argmap::new().booleans(&["verbose","v"]).parse([
+    "--verbose=2", "-v=3", "x"
+  ].iter());
+  assert_eq![args, vec!["x"]];
+  assert_eq![argv, hash([
+    ("verbose",vec!["2"]),
+    ("v",vec!["3"]),
+  ].iter())];
+}
+
+#[test] fn parse_boolean_space_value() {
+  let (args,argv) = argmap::new().booleans(&["verbose","v"]).parse([
+    "--verbose", "2", "-v", "3"
+  ].iter());
+  assert_eq![args, vec!["2","3"]];
+  assert_eq![argv, hash([
+    ("verbose",vec![]),
+    ("v",vec![]),
+  ].iter())];
+}
+
+#[test] fn parse_boolean_char() {
+  let (args,argv) = argmap::new().boolean_char('q').boolean("help").boolean('x').parse([
+    "-q", "a", "--help", "b", "-x", "c"
+  ].iter());
+  assert_eq![args, vec!["a","b","c"]];
+  assert_eq![argv, hash([
+    ("q",vec![]),
+    ("help",vec![]),
+    ("x",vec![]),
+  ].iter())];
+}
+
 #[test] fn parse_booleans_slice() {
   let (args,argv) = argmap::new().booleans(&["q","z"]).parse([
     "-q", "x", "-z", "y"
@@ -159,6 +206,7 @@ use std::collections::HashMap;
   ].iter())];
 }
 
+#[allow(clippy::useless_vec)]
 #[test] fn parse_boolean_vec_ref() {
   let (args,argv) = argmap::new().booleans(&vec!["q","z"]).parse([
     "-q", "x", "-z", "y"
@@ -170,6 +218,1044 @@ use std::collections::HashMap;
   ].iter())];
 }
 
+#[test] fn parse_bytes_nul() {
+  let (args,argv) = argmap::parse_bytes(b"tool\0--long\x005\0-xvf\0whatever.tgz\0hello\0\0");
+  assert_eq![args, vec!["tool","hello",""]];
+  assert_eq![argv, hash([
+    ("long",vec!["5"]),
+    ("x",vec![]),
+    ("v",vec![]),
+    ("f",vec!["whatever.tgz"]),
+  ].iter())];
+  let (args,argv) = argmap::parse_bytes(b"tool\0--long\x005\0-xvf\0whatever.tgz\0hello\0");
+  assert_eq![args, vec!["tool","hello"]];
+  assert_eq![argv.len(), 4];
+  let empty: Vec<String> = vec![];
+  assert_eq![argmap::parse_bytes(b""), (empty,hash([].iter()))];
+}
+
+#[test] fn try_parse_bytes_invalid_utf8() {
+  assert![argmap::try_parse_bytes(b"--x\0\xff\xfe\0").is_err()];
+  assert_eq![argmap::try_parse_bytes(b"--x\0yz\0"), Ok((vec![],hash([("x",vec!["yz"])].iter())))];
+  let (args,argv) = argmap::parse_bytes(b"--x\0\xff\xfe\0");
+  assert![args.is_empty()];
+  assert_eq![argv, hash([("x",vec!["\u{fffd}\u{fffd}"])].iter())];
+}
+
+#[test] fn parse_sequence_order() {
+  use argmap::Token;
+  let tokens = argmap::new().boolean("q").parse_sequence([
+    "one", "--long", "5", "-xvf", "file.tgz", "two", "-q", "three", "--z=6", "--", "-four"
+  ].iter());
+  assert_eq![tokens, vec![
+    Token::Pos("one".to_string()),
+    opt("long", Some("5")),
+    opt("x", None),
+    opt("v", None),
+    opt("f", Some("file.tgz")),
+    Token::Pos("two".to_string()),
+    opt("q", None),
+    Token::Pos("three".to_string()),
+    opt("z", Some("6")),
+    Token::Pos("-four".to_string()),
+  ]];
+  let rebuilt: Vec<String> = tokens.iter().map(|t| match t {
+    Token::Pos(s) => s.clone(),
+    Token::Opt { key, value: None } => format!["--{}", key],
+    Token::Opt { key, value: Some(v) } => format!["--{}={}", key, v],
+  }).collect();
+  assert_eq![rebuilt, vec![
+    "one", "--long=5", "--x", "--v", "--f=file.tgz", "two", "--q", "three", "--z=6", "-four"
+  ]];
+}
+
+fn opt(key: &str, value: Option<&str>) -> argmap::Token {
+  argmap::Token::Opt { key: key.to_string(), value: value.map(|v| v.to_string()) }
+}
+
+#[test] fn parse_until_positional_subcommand() {
+  let (args,argv,rest) = argmap::new().boolean("v").parse_until_positional([
+    "--v", "build", "--release"
+  ].iter());
+  assert_eq![args, vec!["build"]];
+  assert_eq![argv, hash([("v",vec![])].iter())];
+  assert_eq![rest, vec!["--release"]];
+  let (args,argv) = argmap::new().boolean("release").parse(rest.iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([("release",vec![])].iter())];
+}
+
+#[test] fn parse_until_positional_value() {
+  let (args,argv,rest) = argmap::parse_until_positional([
+    "--out", "file", "-x5", "run", "-x", "--", "y"
+  ].iter());
+  assert_eq![args, vec!["run"]];
+  assert_eq![argv, hash([("out",vec!["file"]),("x",vec!["5"])].iter())];
+  assert_eq![rest, vec!["-x","--","y"]];
+  let empty: Vec<String> = vec![];
+  let (args,_argv,rest) = argmap::parse_until_positional(["--q"].iter());
+  assert_eq![args, empty.clone()];
+  assert_eq![rest, empty];
+}
+
+#[test] fn parse_empty_long_key() {
+  let (args,argv) = argmap::parse(["--=foo", "-x", "--=bar"].iter());
+  assert_eq![args, vec!["--=foo"]];
+  assert_eq![argv, hash([("x",vec!["--=bar"])].iter())];
+}
+
+#[test] fn parse_map_value() {
+  let (args,argv) = argmap::new()
+    .map_value("email", Box::new(|s| s.trim().to_lowercase()))
+    .map_value("name", Box::new(|s| s.to_uppercase()))
+    .parse([
+      "--email", " Bob@Example.COM ", "--name=bob", "--name", "--other", "Xy", "pos"
+    ].iter());
+  assert_eq![args, vec!["pos"]];
+  assert_eq![argv, hash([
+    ("email",vec!["bob@example.com"]),
+    ("name",vec!["BOB"]),
+    ("other",vec!["Xy"]),
+  ].iter())];
+}
+
+#[test] fn parse_with_raw_after_verb() {
+  let input = ["-v", "bisect", "run", "--", "./test.sh", "--fast", "-x"];
+  let (args,argv,raw) = argmap::new().boolean("v").raw_after("run").parse_with_raw(input.iter());
+  assert_eq![args, vec!["bisect","run"]];
+  assert_eq![argv, hash([("v",vec![])].iter())];
+  assert_eq![raw, vec!["--","./test.sh","--fast","-x"]];
+  let (args,argv) = argmap::new().boolean("v").raw_after("run").parse(input.iter());
+  assert_eq![args, vec!["bisect","run","--","./test.sh","--fast","-x"]];
+  assert_eq![argv, hash([("v",vec![])].iter())];
+}
+
+#[test] fn parse_with_raw_verb_as_value() {
+  let (args,argv,raw) = argmap::new().raw_after("run").parse_with_raw([
+    "--mode", "run", "--fast"
+  ].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([("mode",vec!["run"]),("fast",vec![])].iter())];
+  assert_eq![raw, Vec::<String>::new()];
+}
+
+#[test] fn parse_long_value_prefix() {
+  let (args,argv) = argmap::new().long_value_prefix(&["O","Wl"]).parse([
+    "--O2", "--Wl,-rpath", "--O", "3", "--O=s", "--Oops", "x"
+  ].iter());
+  assert_eq![args, vec!["x"]];
+  assert_eq![argv, hash([
+    ("O",vec!["2","3","s","ops"]),
+    ("Wl",vec![",-rpath"]),
+  ].iter())];
+  let (args,argv) = argmap::parse(["--Oops"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([("Oops",vec![])].iter())];
+}
+
+#[test] fn parse_first_wins() {
+  let (args,argv) = argmap::new().boolean("q").parse_first([
+    "--port", "80", "x", "--port=8080", "-q", "--port", "443"
+  ].iter());
+  assert_eq![args, vec!["x"]];
+  let expected: HashMap<String,String> = [
+    ("port","80"),
+    ("q",""),
+  ].iter().map(|(k,v)| (k.to_string(), v.to_string())).collect();
+  assert_eq![argv, expected];
+}
+
+#[test] fn parse_reader_whitespace() {
+  let input = std::io::Cursor::new("-x 5\n  --long=yes\tone\n-- -two\n");
+  let (args,argv) = argmap::new().parse_reader(input).unwrap();
+  assert_eq![args, vec!["one","-two"]];
+  assert_eq![argv, hash([("x",vec!["5"]),("long",vec!["yes"])].iter())];
+}
+
+#[test] fn parse_reader_nul() {
+  let input = std::io::Cursor::new(b"-x\0five and six\0one two\0".to_vec());
+  let (args,argv) = argmap::new().parse_reader_nul(input).unwrap();
+  assert_eq![args, vec!["one two"]];
+  assert_eq![argv, hash([("x",vec!["five and six"])].iter())];
+  let input = std::io::Cursor::new(b"-x\0\xff\0".to_vec());
+  let err = argmap::new().parse_reader_nul(input).unwrap_err();
+  assert_eq![err.kind(), std::io::ErrorKind::InvalidData];
+}
+
+#[test] fn parse_reader_matches_parse() {
+  let tokens = ["--long", "5", "-xvf", "file.tgz", "one", "--z=6", "--", "-two"];
+  let input = std::io::Cursor::new(tokens.join(" "));
+  assert_eq![argmap::new().parse_reader(input).unwrap(), argmap::parse(tokens.iter())];
+}
+
+#[test] fn parse_reader_shell_split() {
+  let input = "--msg 'hello world' -x \"a \\\"b\\\"\" c\\ d";
+  let expected = (vec!["c d".to_string()], hash([
+    ("msg",vec!["hello world"]),
+    ("x",vec!["a \"b\""]),
+  ].iter()));
+  let res = argmap::new().shell_split(true).parse_reader(std::io::Cursor::new(input)).unwrap();
+  assert_eq![res, expected];
+  assert_eq![argmap::parse_str(input), Ok(expected)];
+  let (args,argv) = argmap::new().parse_reader(std::io::Cursor::new(input)).unwrap();
+  assert_eq![args, vec!["world'",r#"\"b\"""#,"c\\","d"]];
+  assert_eq![argv, hash([("msg",vec!["'hello"]),("x",vec!["\"a"])].iter())];
+}
+
+#[test] fn parse_reader_shell_split_unterminated() {
+  let input = std::io::Cursor::new("--msg 'hello world");
+  let err = argmap::new().shell_split(true).parse_reader(input).unwrap_err();
+  assert_eq![err.kind(), std::io::ErrorKind::InvalidData];
+  assert_eq![err.into_inner().unwrap().to_string(), argmap::TokenizeError::Unterminated {
+    partial: "hello world".to_string(),
+  }.to_string()];
+}
+
+#[test] fn parse_str_unterminated() {
+  let res = argmap::parse_str("--msg 'hello wor");
+  assert_eq![res, Err(argmap::TokenizeError::Unterminated { partial: "hello wor".to_string() })];
+  let res = argmap::parse_str("-x \"a 'b' c");
+  assert_eq![res, Err(argmap::TokenizeError::Unterminated { partial: "a 'b' c".to_string() })];
+  let (args,argv) = argmap::parse_str("--msg 'hello world'").unwrap();
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([("msg",vec!["hello world"])].iter())];
+}
+
+#[test] fn tokenize_quotes() {
+  assert_eq![argmap::tokenize("  a  ''  \"\" b'c'\"d\" "), vec!["a","","","bcd"]];
+  assert_eq![argmap::tokenize("'unterminated quote"), vec!["unterminated quote"]];
+  assert_eq![argmap::tokenize(r#""\n\\" \"#), vec!["\\n\\","\\"]];
+  assert_eq![argmap::tokenize(""), Vec::<String>::new()];
+}
+
+#[test] fn parse_greedy_until_flag() {
+  let (args,argv) = argmap::new().greedy("msg").greedy("m").boolean("verbose").parse([
+    "--msg", "this", "is", "a", "long", "message", "--verbose", "x",
+    "-m", "short", "one", "-q", "y",
+  ].iter());
+  assert_eq![args, vec!["x"]];
+  assert_eq![argv, hash([
+    ("msg",vec!["this","is","a","long","message"]),
+    ("verbose",vec![]),
+    ("m",vec!["short","one"]),
+    ("q",vec!["y"]),
+  ].iter())];
+}
+
+#[test] fn parse_greedy_until_dashdash() {
+  let (args,argv) = argmap::new().greedy("files").parse([
+    "--files", "a", "b", "--", "c", "-d"
+  ].iter());
+  assert_eq![args, vec!["c","-d"]];
+  assert_eq![argv, hash([("files",vec!["a","b"])].iter())];
+}
+
+#[test] fn parse_value_cluster_final() {
+  let (args,argv) = argmap::new().value("f").parse([
+    "-xf", "out.txt", "pos"
+  ].iter());
+  assert_eq![args, vec!["pos"]];
+  assert_eq![argv, hash([("x",vec![]),("f",vec!["out.txt"])].iter())];
+}
+
+#[test] fn parse_value_cluster_attached() {
+  let (args,argv) = argmap::new().values(&["f","I"]).parse([
+    "-xfout.txt", "pos", "-Iinclude/dir", "-xvf1", "-f", "-9"
+  ].iter());
+  assert_eq![args, vec!["pos"]];
+  assert_eq![argv, hash([
+    ("x",vec![]),
+    ("v",vec![]),
+    ("f",vec!["out.txt","1","-9"]),
+    ("I",vec!["include/dir"]),
+  ].iter())];
+}
+
+#[test] fn parse_pathological_tokens() {
+  let (args,argv) = argmap::parse(["", "---", "x", "---=y", "-", "--n", "", "--m"].iter());
+  assert_eq![args, vec!["","-"]];
+  assert_eq![argv, hash([
+    ("-",vec!["x","y"]),
+    ("n",vec![""]),
+    ("m",vec![]),
+  ].iter())];
+  let (args,argv) = argmap::parse(["---"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([("-",vec![])].iter())];
+}
+
+#[test] fn parse_trim_tokens() {
+  let input = [" --flag ", " value ", "\t-x\n", "--y= z "];
+  let (args,argv) = argmap::new().trim_tokens(true).parse(input.iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([
+    ("flag",vec!["value"]),
+    ("x",vec![]),
+    ("y",vec![" z"]),
+  ].iter())];
+  let (args,argv) = argmap::parse(input.iter());
+  assert_eq![args, vec![" --flag "," value ","\t-x\n"]];
+  assert_eq![argv, hash([("y",vec![" z "])].iter())];
+}
+
+#[test] fn parse_counted_cluster() {
+  let parser = argmap::new().alias("v","verbose").counted("verbose");
+  let expected = hash([("verbose",vec!["3"])].iter());
+  for input in [vec!["-vvv"], vec!["-v","-v","-v"], vec!["--verbose","--verbose","--verbose"]] {
+    let (args,argv) = parser.parse(input.iter());
+    assert_eq![args, Vec::<String>::new()];
+    assert_eq![argv, expected];
+  }
+  let (args,argv) = parser.parse(["-vxv", "file", "--verbose", "-v", "more"].iter());
+  assert_eq![args, vec!["file","more"]];
+  assert_eq![argv, hash([("verbose",vec!["4"]),("x",vec![])].iter())];
+}
+
+#[test] fn parse_alias() {
+  let (args,argv) = argmap::new().alias("o","output").alias("q","quiet").boolean("quiet").parse([
+    "-o", "a.txt", "-q", "b", "--output=c.txt", "--quiet"
+  ].iter());
+  assert_eq![args, vec!["b"]];
+  assert_eq![argv, hash([("output",vec!["a.txt","c.txt"]),("quiet",vec![])].iter())];
+}
+
+#[test] fn parse_flat_pairs() {
+  let pairs = argmap::new().boolean("q").parse_flat([
+    "one", "--name", "bob", "-q", "two", "--tag=a", "--tag=b"
+  ].iter());
+  let expected: Vec<(String,String)> = [
+    ("","one"),
+    ("name","bob"),
+    ("q",""),
+    ("","two"),
+    ("tag","a"),
+    ("tag","b"),
+  ].iter().map(|(k,v)| (k.to_string(), v.to_string())).collect();
+  assert_eq![pairs, expected];
+}
+
+#[test] fn parse_bool_vs_empty_matrix() {
+  let cases = vec![
+    ("--k", vec![("k",vec![])]),
+    ("--k=", vec![("k",vec![""])]),
+    ("-k", vec![("k",vec![])]),
+    ("-k=", vec![("k",vec![""])]),
+    ("-xk", vec![("x",vec![]),("k",vec![])]),
+    ("-xk=", vec![("x",vec![]),("k",vec![""])]),
+    ("-xk=v", vec![("x",vec![]),("k",vec!["v"])]),
+  ];
+  for (token,expected) in cases {
+    let (args,argv) = argmap::parse([token].iter());
+    assert_eq![args, Vec::<String>::new(), "{}", token];
+    assert_eq![argv, hash(expected.iter()), "{}", token];
+  }
+}
+
+#[test] fn parse_cluster_equals() {
+  let (args,argv) = argmap::new().value("I").parse([
+    "-qrs=1234", "-x5=6", "-Iinc=1", "-=y", "z"
+  ].iter());
+  assert_eq![args, vec!["-=y","z"]];
+  assert_eq![argv, hash([
+    ("q",vec![]),
+    ("r",vec![]),
+    ("s",vec!["1234"]),
+    ("x",vec!["5=6"]),
+    ("I",vec!["inc=1"]),
+  ].iter())];
+}
+
+#[test] fn parse_make_jobs() {
+  let expected = |v: &str| hash([("j",vec![v])].iter());
+  assert_eq![argmap::parse(["-j4"].iter()), (vec![],expected("4"))];
+  assert_eq![argmap::parse(["-j4k"].iter()), (vec![],expected("4k"))];
+  assert_eq![argmap::parse(["-j","4"].iter()), (vec![],expected("4"))];
+  assert_eq![argmap::parse(["-j16"].iter()), (vec![],expected("16"))];
+  assert_eq![argmap::parse(["-kj4"].iter()), (vec![],hash([("k",vec![]),("j",vec!["4"])].iter()))];
+}
+
+#[test] fn parse_no_clustering() {
+  let input = ["-output", "file", "-xvf", "-n", "-5", "-name=x", "pos"];
+  let (args,argv) = argmap::new().no_clustering(true).parse(input.iter());
+  assert_eq![args, vec!["pos"]];
+  assert_eq![argv, hash([
+    ("output",vec!["file"]),
+    ("xvf",vec![]),
+    ("n",vec!["-5"]),
+    ("name",vec!["x"]),
+  ].iter())];
+  let (args,argv) = argmap::parse(input.iter());
+  assert_eq![args, vec!["pos"]];
+  assert_eq![argv, hash([
+    ("o",vec![]),
+    ("u",vec![]),
+    ("t",vec!["file"]),
+    ("p",vec![]),
+    ("x",vec![]),
+    ("v",vec![]),
+    ("f",vec![]),
+    ("n",vec!["-5"]),
+    ("a",vec![]),
+    ("m",vec![]),
+    ("e",vec!["x"]),
+  ].iter())];
+}
+
+#[test] fn parse_program_name() {
+  let (program,args,argv) = argmap::parse_program([
+    "/usr/bin/tool", "-x", "5", "one", "two"
+  ].iter());
+  assert_eq![program, Some("/usr/bin/tool".to_string())];
+  assert_eq![args, vec!["one","two"]];
+  assert_eq![argv, hash([("x",vec!["5"])].iter())];
+  let empty: Vec<String> = vec![];
+  assert_eq![argmap::parse_program(empty.iter()), (None,vec![],hash([].iter()))];
+}
+
+#[test] fn parse_with_custom_hasher() {
+  use std::hash::BuildHasherDefault;
+  use std::collections::hash_map::DefaultHasher;
+  type H = BuildHasherDefault<DefaultHasher>;
+  let input = ["--long", "5", "-xvf", "file.tgz", "one"];
+  let (args,argv) = argmap::new().parse_with_hasher::<H,_>(input.iter());
+  assert_eq![args, vec!["one"]];
+  let (_,expected) = argmap::parse(input.iter());
+  assert_eq![argv.len(), expected.len()];
+  for (k,v) in expected.iter() {
+    assert_eq![argv.get(k), Some(v)];
+  }
+}
+
+#[test] fn parse_split_on_one_key() {
+  let (args,argv) = argmap::new().split_on("tags", ',').parse([
+    "--tags", "a,b", "--path", "a,b", "--tags", "c", "-x", "1,2"
+  ].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([
+    ("tags",vec!["a","b","c"]),
+    ("path",vec!["a,b"]),
+    ("x",vec!["1,2"]),
+  ].iter())];
+}
+
+#[test] fn parse_split_on_with_map_value() {
+  let (_args,argv) = argmap::new()
+    .split_on("tags", ',')
+    .map_value("tags", Box::new(|s| s.trim().to_uppercase()))
+    .parse(["--tags", "a, b"].iter());
+  assert_eq![argv, hash([("tags",vec!["A","B"])].iter())];
+}
+
+#[test] fn parse_split_positionals() {
+  let (args,_argv) = argmap::parse(["a", "-x", "b", "to", "c", "to", "d"].iter());
+  assert_eq![
+    argmap::split_positionals(&args, "to"),
+    (vec!["a".to_string()], vec!["c".to_string(),"to".to_string(),"d".to_string()])
+  ];
+  let args = vec!["a".to_string(),"b".to_string()];
+  assert_eq![argmap::split_positionals(&args, "to"), (args.clone(), vec![])];
+}
+
+#[test] fn parse_promote_kv_positionals() {
+  let (args,argv) = argmap::new().promote_kv_positionals(true).parse([
+    "name=bob", "a=b=c", "x/y=z", "=q", "-o", "k=v", "plain", "--", "after=1"
+  ].iter());
+  assert_eq![args, vec!["x/y=z","=q","plain","after=1"]];
+  assert_eq![argv, hash([
+    ("name",vec!["bob"]),
+    ("a",vec!["b=c"]),
+    ("o",vec!["k=v"]),
+  ].iter())];
+  let (args,argv) = argmap::parse(["name=bob"].iter());
+  assert_eq![args, vec!["name=bob"]];
+  assert_eq![argv, hash([].iter())];
+}
+
+#[test] fn parse_env_prepended() {
+  std::env::set_var("ARGMAP_TEST_PREPEND_ENV", "-v --color=never 'in file'");
+  let parser = argmap::new().boolean("v").prepend_env("ARGMAP_TEST_PREPEND_ENV");
+  let (args,argv) = parser.parse_env(["--color=always", "out"].iter()).unwrap();
+  assert_eq![args, vec!["in file","out"]];
+  assert_eq![argv, hash([
+    ("v",vec![]),
+    ("color",vec!["never","always"]),
+  ].iter())];
+  let (args,argv) = argmap::new().prepend_env("ARGMAP_TEST_PREPEND_ENV_UNSET")
+    .parse_env(["-x"].iter()).unwrap();
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([("x",vec![])].iter())];
+}
+
+#[test] fn parse_env_unterminated() {
+  std::env::set_var("ARGMAP_TEST_PREPEND_ENV_QUOTE", r#"--x "unterminated"#);
+  let parser = argmap::new().prepend_env("ARGMAP_TEST_PREPEND_ENV_QUOTE");
+  assert_eq![parser.parse_env(["-y"].iter()), Err(argmap::TokenizeError::Unterminated {
+    partial: "unterminated".to_string(),
+  })];
+}
+
+#[test] fn parse_backslash_escape() {
+  let input = [r"\-weird", "-x", r"\-y", r"\\-z", r"\n", "--", r"\-q"];
+  let (args,argv) = argmap::new().backslash_escape(true).parse(input.iter());
+  assert_eq![args, vec!["-weird",r"\-z",r"\n",r"\-q"]];
+  assert_eq![argv, hash([("x",vec!["-y"])].iter())];
+  let (args,argv) = argmap::parse(input.iter());
+  assert_eq![args, vec![r"\-weird",r"\\-z",r"\n",r"\-q"]];
+  assert_eq![argv, hash([("x",vec![r"\-y"])].iter())];
+}
+
+#[test] fn parse_autocorrect_dashes() {
+  let input = ["\u{2014}flag", "x", "\u{2013}other=y", "a\u{2014}b", "--", "\u{2014}z"];
+  let (args,argv) = argmap::new().autocorrect_dashes(true).parse(input.iter());
+  assert_eq![args, vec!["a\u{2014}b","\u{2014}z"]];
+  assert_eq![argv, hash([("flag",vec!["x"]),("other",vec!["y"])].iter())];
+  let (args,argv) = argmap::parse(input.iter());
+  assert_eq![args, vec!["\u{2014}flag","x","\u{2013}other=y","a\u{2014}b","\u{2014}z"]];
+  assert_eq![argv, hash([].iter())];
+}
+
+#[test] fn parse_positionals_from_str() {
+  let (_program,args,_argv) = argmap::parse_program(["sum", "1", "-v", "2", "x", "-3"].iter());
+  let nums = argmap::parse_positionals::<i32>(&args);
+  assert_eq![args, vec!["1","x"]];
+  assert_eq![nums.len(), 2];
+  assert_eq![nums[0], Ok(1)];
+  assert![nums[1].is_err()];
+  let (_program,args,_argv) = argmap::parse_program(["sum", "1", "2", "--", "-3"].iter());
+  let nums: Result<Vec<i32>,_> = argmap::parse_positionals(&args).into_iter().collect();
+  assert_eq![nums, Ok(vec![1,2,-3])];
+}
+
+#[test] fn parse_terminating_flags() {
+  let parser = argmap::new().boolean("v").terminating_flags(&["exec","e"]);
+  let (args,argv) = parser.parse(["-v", "--exec", "ls", "-l", "--all", "--"].iter());
+  assert_eq![args, vec!["-l","--all","--"]];
+  assert_eq![argv, hash([("v",vec![]),("exec",vec!["ls"])].iter())];
+  let (args,argv) = parser.parse(["--exec", "-l", "x"].iter());
+  assert_eq![args, vec!["-l","x"]];
+  assert_eq![argv, hash([("exec",vec![])].iter())];
+  let (args,argv) = parser.parse(["-ve=ls", "-v", "x"].iter());
+  assert_eq![args, vec!["-v","x"]];
+  assert_eq![argv, hash([("v",vec![]),("e",vec!["ls"])].iter())];
+  let (_args,_argv,meta) = parser.parse_with_meta(["a", "--exec", "-l"].iter());
+  assert_eq![meta.passthrough_from, Some(1)];
+}
+
+#[test] fn parse_debug_format() {
+  let (args,argv) = argmap::parse(["-z", "5", "--msg", "cool", "one", "-xv", "-a"].iter());
+  assert_eq![
+    argmap::debug_format(&args, &argv),
+    "args=[\"one\"]\nargv={\"a\": [], \"msg\": [\"cool\"], \"v\": [], \"x\": [], \"z\": [\"5\"]}"
+  ];
+}
+
+#[test] fn parse_lone_dash_after_pending_key() {
+  let (args,argv) = argmap::parse(["--input", "-"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([("input",vec!["-"])].iter())];
+  let (args,argv) = argmap::parse(["--flag", "-", "positional"].iter());
+  assert_eq![args, vec!["positional"]];
+  assert_eq![argv, hash([("flag",vec!["-"])].iter())];
+  let (args,argv) = argmap::new().boolean("flag").parse(["--flag", "-", "positional"].iter());
+  assert_eq![args, vec!["-","positional"]];
+  assert_eq![argv, hash([("flag",vec![])].iter())];
+}
+
+#[test] fn parse_rename_keys() {
+  let (args,argv) = argmap::new()
+    .boolean("dry-run")
+    .counted("v")
+    .alias("level","log-level")
+    .map_value("log-level", Box::new(|s| s.to_uppercase()))
+    .rename_keys(Box::new(|k| k.replace('-', "_")))
+    .parse(["--log-level", "debug", "--dry-run", "file-name", "--level=info", "-vv"].iter());
+  assert_eq![args, vec!["file-name"]];
+  assert_eq![argv, hash([
+    ("log_level",vec!["DEBUG","INFO"]),
+    ("dry_run",vec![]),
+    ("v",vec!["2"]),
+  ].iter())];
+}
+
+#[test] fn parse_kv_separators() {
+  let input = ["--a=1", "--b:2", "--c=x:y", "--d:x=y", "-e:5", "-:z", "--f", "g"];
+  let (args,argv) = argmap::new().kv_separators(&['=',':']).parse(input.iter());
+  assert_eq![args, vec!["-:z"]];
+  assert_eq![argv, hash([
+    ("a",vec!["1"]),
+    ("b",vec!["2"]),
+    ("c",vec!["x:y"]),
+    ("d",vec!["x=y"]),
+    ("e",vec!["5"]),
+    ("f",vec!["g"]),
+  ].iter())];
+  let (args,argv) = argmap::parse(input.iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([
+    ("a",vec!["1"]),
+    ("b:2",vec![]),
+    ("c",vec!["x:y"]),
+    ("d:x",vec!["y"]),
+    ("e",vec![":5"]),
+    (":",vec![]),
+    ("z",vec![]),
+    ("f",vec!["g"]),
+  ].iter())];
+}
+
+#[test] fn parse_split_on_equals_form() {
+  let (args,argv) = argmap::new()
+    .split_on("tags", ',')
+    .split_on("t", ',')
+    .map_value("t", Box::new(|s| s.to_uppercase()))
+    .parse(["--tags=a,b,c", "-t=x,y"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([
+    ("tags",vec!["a","b","c"]),
+    ("t",vec!["X","Y"]),
+  ].iter())];
+}
+
+#[test] fn parse_trailing_equals_is_empty_value() {
+  let input = ["--feature=", "next", "-f=", "other", "--msg=", "more"];
+  let (args,argv) = argmap::parse(input.iter());
+  assert_eq![args, vec!["next","other","more"]];
+  assert_eq![argv, hash([("feature",vec![""]),("f",vec![""]),("msg",vec![""])].iter())];
+  let (args,argv) = argmap::new()
+    .values(&["feature","f"])
+    .greedy("msg")
+    .long_value_prefix(&["feature"])
+    .parse(input.iter());
+  assert_eq![args, vec!["next","other","more"]];
+  assert_eq![argv, hash([("feature",vec![""]),("f",vec![""]),("msg",vec![""])].iter())];
+}
+
+#[test] fn parse_to_argv_round_trip() {
+  use argmap::BoolStyle;
+  let input = ["-v", "--msg", "hi there", "-n", "-5", "one", "--flag", "--", "-two"];
+  let (args,argv) = argmap::new().boolean("flag").parse(input.iter());
+  let out = argmap::to_argv(&args, &argv, BoolStyle::Bare);
+  assert_eq![out, vec!["--flag","--msg=hi there","-n=-5","-v","--","one","-two"]];
+  assert_eq![argmap::parse(out.iter()), (args.clone(),argv.clone())];
+  let out = argmap::to_argv(&args, &argv, BoolStyle::True);
+  assert_eq![out, vec!["--flag=true","--msg=hi there","-n=-5","-v=true","--","one","-two"]];
+}
+
+#[test] fn parse_negatable_counted() {
+  let parser = argmap::new().counted("verbose").alias("v","verbose").negatable("verbose");
+  let (_args,argv) = parser.parse(["-vvv", "--no-verbose"].iter());
+  assert_eq![argv, hash([("verbose",vec!["2"])].iter())];
+  let (_args,argv) = parser.parse(["-v", "--no-verbose", "--no-v", "-vv", "file"].iter());
+  assert_eq![argv, hash([("verbose",vec!["2"])].iter())];
+  let (args,argv) = parser.parse(["--no-verbose", "file", "-v", "--no-verbose"].iter());
+  assert_eq![args, vec!["file"]];
+  assert_eq![argv, hash([].iter())];
+}
+
+#[test] fn parse_negatable_removes_values() {
+  let parser = argmap::new().negatable("color");
+  let (args,argv) = parser.parse(["--color=red", "--no-color", "x", "--color", "blue"].iter());
+  assert_eq![args, vec!["x"]];
+  assert_eq![argv, hash([("color",vec!["blue"])].iter())];
+  let (_args,argv) = parser.parse(["--color=red", "--no-color"].iter());
+  assert_eq![argv, hash([].iter())];
+  let (_args,argv) = argmap::parse(["--no-color", "x"].iter());
+  assert_eq![argv, hash([("no-color",vec!["x"])].iter())];
+}
+
+#[test] fn parse_into_entries() {
+  use argmap::Entry;
+  let (args,argv) = argmap::parse(["one", "-x", "5", "--flag", "--", "two"].iter());
+  let entries: Vec<Entry> = argmap::into_entries(args.clone(), argv.clone()).collect();
+  assert_eq![&entries[0..2], &[Entry::Positional("one".into()), Entry::Positional("two".into())]];
+  let mut rebuilt = (vec![],HashMap::new());
+  for entry in entries {
+    match entry {
+      Entry::Positional(s) => rebuilt.0.push(s),
+      Entry::Flag(k,v) => { rebuilt.1.insert(k,v); },
+    }
+  }
+  assert_eq![rebuilt, (args,argv)];
+}
+
+#[test] fn parse_greedy_stops_at_known() {
+  let parser = argmap::new().greedy("files").boolean("v").known(&["verbose"]);
+  let (args,argv) = parser.parse(["--files", "a", "-x", "b", "-v", "c"].iter());
+  assert_eq![args, vec!["c"]];
+  assert_eq![argv, hash([("files",vec!["a","-x","b"]),("v",vec![])].iter())];
+  let (args,argv) = parser.parse(["--files", "a", "--other=1", "--verbose", "--", "-v"].iter());
+  assert_eq![args, vec!["-v"]];
+  assert_eq![argv, hash([("files",vec!["a","--other=1"]),("verbose",vec![])].iter())];
+  let (args,argv) = argmap::new().greedy("files").parse(["--files", "a", "-x", "b"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([("files",vec!["a"]),("x",vec!["b"])].iter())];
+}
+
+#[test] fn parse_long_case_insensitive() {
+  let parser = argmap::new().long_case_insensitive(true).boolean("verbose");
+  let (args,argv) = parser.parse([
+    "--Verbose", "file", "--VERBOSE", "--Name=Bob", "-V", "-v", "X"
+  ].iter());
+  assert_eq![args, vec!["file"]];
+  assert_eq![argv, hash([
+    ("verbose",vec![]),
+    ("name",vec!["Bob"]),
+    ("V",vec![]),
+    ("v",vec!["X"]),
+  ].iter())];
+  let (_args,argv) = argmap::parse(["--Verbose=1", "--verbose=2"].iter());
+  assert_eq![argv, hash([("Verbose",vec!["1"]),("verbose",vec!["2"])].iter())];
+}
+
+#[test] fn parse_normalize_dashes() {
+  let (args,argv) = argmap::new().normalize_dashes(true).boolean("dry-run").parse([
+    "--log-level", "info-level", "--dry-run", "a-b", "---x-y-"
+  ].iter());
+  assert_eq![args, vec!["a-b"]];
+  assert_eq![argv, hash([
+    ("log_level",vec!["info-level"]),
+    ("dry_run",vec![]),
+    ("-x_y-",vec![]),
+  ].iter())];
+}
+
+#[test] fn parse_map_all_values() {
+  let (args,argv) = argmap::new()
+    .map_value("out", Box::new(|s| s + "/out"))
+    .map_all_values(Box::new(|s| match s.strip_prefix('~') {
+      Some(rest) => format!["/home/me{}", rest],
+      None => s,
+    }))
+    .parse(["--in", "~/a", "-x=~", "--out", "~", "~/pos", "--flag"].iter());
+  assert_eq![args, vec!["~/pos"]];
+  assert_eq![argv, hash([
+    ("in",vec!["/home/me/a"]),
+    ("x",vec!["/home/me"]),
+    ("out",vec!["/home/me/out"]),
+    ("flag",vec![]),
+  ].iter())];
+}
+
+#[test] fn parse_interpolate_env() {
+  std::env::set_var("ARGMAP_TEST_INTERPOLATE", "/home/me");
+  let input = [
+    "--a", "$ARGMAP_TEST_INTERPOLATE/bin", "--b=${ARGMAP_TEST_INTERPOLATE}x",
+    "--c", "$ARGMAP_TEST_UNSET:${ARGMAP_TEST_UNSET}", "--d", "$$5 $ ${x", "$ARGMAP_TEST_INTERPOLATE",
+  ];
+  let (args,argv) = argmap::new().interpolate_env(true).parse(input.iter());
+  assert_eq![args, vec!["$ARGMAP_TEST_INTERPOLATE"]];
+  assert_eq![argv, hash([
+    ("a",vec!["/home/me/bin"]),
+    ("b",vec!["/home/mex"]),
+    ("c",vec!["$ARGMAP_TEST_UNSET:${ARGMAP_TEST_UNSET}"]),
+    ("d",vec!["$5 $ ${x"]),
+  ].iter())];
+  let (_args,argv) = argmap::new().interpolate_env(true).unknown_env_empty(true)
+    .parse(input.iter());
+  assert_eq![argv.get("c"), Some(&vec![":".to_string()])];
+  let (_args,argv) = argmap::parse(input.iter());
+  assert_eq![argv.get("a"), Some(&vec!["$ARGMAP_TEST_INTERPOLATE/bin".to_string()])];
+}
+
+#[test] fn parse_sorted_keys() {
+  let (args,argv) = argmap::new().parse_sorted(["-z", "1", "--b", "-a", "x", "--m=2"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv.keys().collect::<Vec<_>>(), vec!["a","b","m","z"]];
+  assert_eq![argv.get("a"), Some(&vec!["x".to_string()])];
+  assert_eq![format!["{:?}", argv], r#"{"a": ["x"], "b": [], "m": ["2"], "z": ["1"]}"#];
+}
+
+#[test] fn parse_positional_names() {
+  let parser = argmap::new().positional_names(&["src","dst"]).boolean("v");
+  let (args,argv) = parser.parse(["-v", "a", "b", "c"].iter());
+  assert_eq![args, vec!["a","b","c"]];
+  assert_eq![argv, hash([("v",vec![]),("src",vec!["a"]),("dst",vec!["b"])].iter())];
+  let (args,argv) = parser.parse(["a"].iter());
+  assert_eq![args, vec!["a"]];
+  assert_eq![argv, hash([("src",vec!["a"])].iter())];
+}
+
+#[test] fn parse_rest_key() {
+  let parser = argmap::new().positional_names(&["src"]).rest_key("files");
+  let (args,argv) = parser.parse(["a", "b", "-x", "c", "d"].iter());
+  assert_eq![args, vec!["a","b","d"]];
+  assert_eq![argv, hash([("src",vec!["a"]),("files",vec!["b","d"]),("x",vec!["c"])].iter())];
+  let (_args,argv) = parser.parse(["a"].iter());
+  assert_eq![argv, hash([("src",vec!["a"])].iter())];
+  let (_args,argv) = argmap::new().rest_key("files").parse(["a", "b"].iter());
+  assert_eq![argv, hash([("files",vec!["a","b"])].iter())];
+}
+
+#[test] fn parse_counted_large_cluster() {
+  let cluster = format!["-{}", "v".repeat(200_000)];
+  let (args,argv) = argmap::new().counted("v").parse([cluster.as_str(), "file"].iter());
+  assert_eq![args, vec!["file"]];
+  assert_eq![argv, hash([("v",vec!["200000"])].iter())];
+}
+
+#[test] fn parse_short_flag_predicate() {
+  let input = ["-5", "-x", "-3.5", "-v", "--", "-7"];
+  let (args,argv) = argmap::new().booleans(&["v"]).parse(input.iter());
+  assert_eq![args, vec!["-7"]];
+  assert_eq![argv, hash([
+    ("5",vec![]),
+    ("x",vec!["-3.5"]),
+    ("v",vec![]),
+  ].iter())];
+  let parser = argmap::new()
+    .booleans(&["v","x"])
+    .short_flag_predicate(Box::new(|s| !s[1..].starts_with(|c: char| c.is_ascii_digit())));
+  let (args,argv) = parser.parse(input.iter());
+  assert_eq![args, vec!["-5","-3.5","-7"]];
+  assert_eq![argv, hash([("x",vec![]),("v",vec![])].iter())];
+  let (args,argv) = parser.parse(["--n", "-2", "-q"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([("n",vec!["-2"]),("q",vec![])].iter())];
+  let flags: HashSet<String> = ["-v","-q"].iter().map(|s| s.to_string()).collect();
+  let parser = argmap::new()
+    .booleans(&["v"])
+    .short_flag_predicate(Box::new(move |s| flags.contains(s)));
+  let (args,argv) = parser.parse(["-v", "-x"].iter());
+  assert_eq![args, vec!["-x"]];
+  assert_eq![argv, hash([("v",vec![])].iter())];
+}
+
+#[test] fn parse_implies() {
+  let parser = argmap::new()
+    .booleans(&["debug","verbose"])
+    .implies("debug", &["verbose","log-level=debug"]);
+  let (args,argv) = parser.parse(["--debug","x"].iter());
+  assert_eq![args, vec!["x"]];
+  assert_eq![argv, hash([
+    ("debug",vec![]),
+    ("verbose",vec![]),
+    ("log-level",vec!["debug"]),
+  ].iter())];
+  let (_args,argv) = parser.parse(["--log-level","warn","--debug"].iter());
+  assert_eq![argv, hash([
+    ("debug",vec![]),
+    ("verbose",vec![]),
+    ("log-level",vec!["warn"]),
+  ].iter())];
+  let (_args,argv) = parser.parse(["--verbose"].iter());
+  assert_eq![argv, hash([("verbose",vec![])].iter())];
+  let parser = argmap::new().implies("debug", &["v"]).negatable("debug");
+  let (_args,argv) = parser.parse(["--debug", "--no-debug"].iter());
+  assert_eq![argv, hash([].iter())];
+  let (_args,argv) = parser.parse(["--no-debug", "--debug"].iter());
+  assert_eq![argv, hash([("debug",vec![]),("v",vec![])].iter())];
+}
+
+#[test] fn parse_array_syntax() {
+  let input = ["--tag[]=a", "--tag[]", "b", "--id[1]=y", "--id[0]=x", "--x[a]=1", "--[]=2"];
+  let (args,argv) = argmap::new().array_syntax(true).parse(input.iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([
+    ("tag",vec!["a","b"]),
+    ("id",vec!["y","x"]),
+    ("x[a]",vec!["1"]),
+    ("[]",vec!["2"]),
+  ].iter())];
+  let (_args,argv) = argmap::new().parse(["--tag[]=a"].iter());
+  assert_eq![argv, hash([("tag[]",vec!["a"])].iter())];
+}
+
+#[test] fn parse_value_list_terminator() {
+  let parser = argmap::new().greedy("files").boolean("verbose").value_list_terminator(";");
+  let input = ["--files", "a", "b", ";", "--verbose", "c", ";", "--files", "d", ";"];
+  let (args,argv) = parser.parse(input.iter());
+  assert_eq![args, vec!["c", ";"]];
+  assert_eq![argv, hash([
+    ("files",vec!["a","b","d"]),
+    ("verbose",vec![]),
+  ].iter())];
+  let (args,argv) = parser.parse(["--files", "a", ";", "b"].iter());
+  assert_eq![args, vec!["b"]];
+  assert_eq![argv, hash([("files",vec!["a"])].iter())];
+}
+
+#[test] fn parse_flags_only() {
+  let input = ["one", "-xvf", "file", "two", "--n=5", "--", "--three", "-z"];
+  let argv = argmap::new().parse_flags_only(input.iter());
+  assert_eq![argv, hash([
+    ("x",vec![]),
+    ("v",vec![]),
+    ("f",vec!["file"]),
+    ("n",vec!["5"]),
+  ].iter())];
+  assert_eq![argv, argmap::parse(input.iter()).1];
+  let parser = argmap::new().positional_names(&["src"]);
+  assert_eq![parser.parse_flags_only(["a", "-q"].iter()), hash([("q",vec![])].iter())];
+}
+
+#[test] fn parse_command_split() {
+  let input = ["tool", "run", "-v", "a", "b"];
+  let (_program,args,argv) = argmap::new().boolean("v").parse_program(input.iter());
+  let cmd = argmap::command_split(args, argv).unwrap();
+  assert_eq![cmd.name, "run"];
+  assert_eq![cmd.args, vec!["a","b"]];
+  assert_eq![cmd.argv, hash([("v",vec![])].iter())];
+  let (args,argv) = argmap::parse(["-v"].iter());
+  assert_eq![argmap::command_split(args, argv), None];
+}
+
+#[test] fn parse_dash_in_cluster() {
+  let (args,argv) = argmap::parse(["-c-6", "-abc-5"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([
+    ("a",vec![]),
+    ("b",vec![]),
+    ("c",vec!["-6","-5"]),
+  ].iter())];
+}
+
+#[test] fn parse_short_break_fn() {
+  let parser = argmap::new().short_break_fn(Box::new(|s| s.chars().all(|c| c.is_ascii_digit())));
+  let (args,argv) = parser.parse(["-abc5", "-xy+z", "-c-6"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([
+    ("a",vec![]),
+    ("b",vec![]),
+    ("c",vec!["5"]),
+    ("x",vec![]),
+    ("y",vec![]),
+    ("+",vec![]),
+    ("z",vec![]),
+    ("-",vec!["6"]),
+  ].iter())];
+}
+
+#[test] fn parse_strip_quotes() {
+  let input = [
+    "--a=\"hello\"", "--b", "'hi'", "--c=\"unbalanced", "--d='mixed\"", "--e=\"\"", "--f='",
+    "'pos'",
+  ];
+  let (args,argv) = argmap::new().strip_quotes(true).parse(input.iter());
+  assert_eq![args, vec!["'pos'"]];
+  assert_eq![argv, hash([
+    ("a",vec!["hello"]),
+    ("b",vec!["hi"]),
+    ("c",vec!["\"unbalanced"]),
+    ("d",vec!["'mixed\""]),
+    ("e",vec![""]),
+    ("f",vec!["'"]),
+  ].iter())];
+  let (_args,argv) = argmap::parse(input.iter());
+  assert_eq![argv.get("a"), Some(&vec!["\"hello\"".to_string()])];
+}
+
+#[test] fn parse_partial() {
+  let parser = argmap::new().boolean("verbose").value("out").booleans(&["v","x"]).known(&["n"]);
+  let input = [
+    "--verbose", "--plugin-opt", "x", "--out", "o.txt", "file", "-p", "-vx", "-vq", "-n5",
+    "--color=auto", "--n", "-3", "--", "--zzz",
+  ];
+  let (args,argv,leftover) = parser.parse_partial(input.iter());
+  assert_eq![args, vec!["x","file","--zzz"]];
+  assert_eq![argv, hash([
+    ("verbose",vec![]),
+    ("out",vec!["o.txt"]),
+    ("v",vec![]),
+    ("x",vec![]),
+    ("n",vec!["5","-3"]),
+  ].iter())];
+  assert_eq![leftover, vec!["--plugin-opt","-p","-vq","--color=auto"]];
+  let (args,_argv,leftover) = parser.leftover_values(true).parse_partial(input.iter());
+  assert_eq![args, vec!["file","--zzz"]];
+  assert_eq![leftover, vec!["--plugin-opt","x","-p","-vq","--color=auto"]];
+}
+
+#[test] fn parse_partial_ends_pending_key() {
+  let parser = argmap::new().known(&["v","o"]);
+  let (args,argv,leftover) = parser.parse_partial(["-v","--plugin","x"].iter());
+  assert_eq![args, vec!["x"]];
+  assert_eq![argv, hash([("v",vec![])].iter())];
+  assert_eq![leftover, vec!["--plugin"]];
+  let (args,argv,leftover) = parser.leftover_values(true).parse_partial(["-v","--p2=1","y"].iter());
+  assert_eq![args, vec!["y"]];
+  assert_eq![argv, hash([("v",vec![])].iter())];
+  assert_eq![leftover, vec!["--p2=1"]];
+}
+
+#[test] fn parse_promote_positional_keys() {
+  let parser = argmap::new().boolean("x").promote_positional_keys(&["name","log-level"]);
+  let input = ["name=bob", "foo=bar", "log-level=debug", "-x", "name=al=ice", "=name"];
+  let (args,argv) = parser.parse(input.iter());
+  assert_eq![args, vec!["foo=bar","=name"]];
+  assert_eq![argv, hash([
+    ("name",vec!["bob","al=ice"]),
+    ("log-level",vec!["debug"]),
+    ("x",vec![]),
+  ].iter())];
+  assert_eq![parser.token_kind("name=bob", None), argmap::TokenKind::Long];
+  assert_eq![parser.token_kind("foo=bar", None), argmap::TokenKind::Positional];
+  let parser = argmap::new().kv_separators(&[':']).promote_positional_keys(&["name"]);
+  let (args,argv) = parser.parse(["name:bob", "name=al", "foo:bar"].iter());
+  assert_eq![args, vec!["name=al","foo:bar"]];
+  assert_eq![argv, hash([("name",vec!["bob"])].iter())];
+  assert_eq![parser.token_kind("name:bob", None), argmap::TokenKind::Long];
+  assert_eq![parser.token_kind("name=bob", None), argmap::TokenKind::Positional];
+  let (args,argv) = argmap::new().kv_separators(&[':']).promote_kv_positionals(true)
+    .parse(["a:b=c", "x=y"].iter());
+  assert_eq![args, vec!["x=y"]];
+  assert_eq![argv, hash([("a",vec!["b=c"])].iter())];
+}
+
+#[test] fn parse_borrowed() {
+  use std::borrow::Cow;
+  let line = String::from("-xvf file --msg=hi pos --n 5 -- -z");
+  let input: Vec<&str> = line.split(' ').collect();
+  let parser = argmap::new().counted("v");
+  let (args,argv) = parser.parse_borrowed(input.iter().copied());
+  let (owned_args,owned_argv) = parser.parse(input.iter());
+  assert_eq![args, owned_args];
+  assert_eq![argv.len(), owned_argv.len()];
+  for (k,v) in owned_argv.iter() {
+    assert_eq![argv.get(k.as_str()), Some(&v.iter().map(|s| Cow::from(s.as_str())).collect())];
+  }
+  assert![args.iter().all(|s| matches![s, Cow::Borrowed(_)])];
+  assert![matches![argv.get("msg").unwrap()[0], Cow::Borrowed("hi")]];
+  assert![matches![argv.get("v").unwrap()[0], Cow::Owned(_)]];
+  let range = line.as_bytes().as_ptr_range();
+  for (k,v) in argv.iter().filter(|(k,_)| *k != "v") {
+    assert![matches![k, Cow::Borrowed(_)] && range.contains(&k.as_ptr())];
+    assert![v.iter().all(|s| matches![s, Cow::Borrowed(_)] && range.contains(&s.as_ptr()))];
+  }
+  let (args,argv) = parser.parse_borrowed(["-vvv", "3"].iter().copied());
+  assert_eq![args, vec!["3"]];
+  assert![matches![args[0], Cow::Borrowed(_)]];
+  assert_eq![argv.get("v"), Some(&vec![Cow::from("3")])];
+  assert![matches![argv.get("v").unwrap()[0], Cow::Owned(_)]];
+}
+
+#[test] fn parse_keep_first() {
+  let parser = argmap::new().keep_first(&["color","q"]).alias("c","color");
+  let input = ["--color=auto", "-c", "never", "--color", "always", "--x=1", "--x=2", "-q", "-q=1"];
+  let (args,argv) = parser.parse(input.iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([
+    ("color",vec!["auto"]),
+    ("x",vec!["1","2"]),
+    ("q",vec![]),
+  ].iter())];
+}
+
+#[test] fn parse_auto_alias_pairs() {
+  let parser = argmap::new()
+    .auto_alias_pairs(&[("v","verbose"),("o","output"),("n","lines")])
+    .boolean("verbose");
+  let input = ["-vo", "a.txt", "--verbose", "--output=b.txt", "-n5", "--lines", "6", "x"];
+  let (args,argv) = parser.parse(input.iter());
+  assert_eq![args, vec!["x"]];
+  assert_eq![argv, hash([
+    ("verbose",vec![]),
+    ("output",vec!["a.txt","b.txt"]),
+    ("lines",vec!["5","6"]),
+  ].iter())];
+  let aliased = argmap::new().alias("v","verbose").alias("o","output").alias("n","lines");
+  assert![parser == aliased.boolean("verbose")];
+}
+
 fn hash<'a>(i: impl Iterator<Item=&'a (&'a str,Vec<&'a str>)>) -> HashMap<String,Vec<String>> {
   i.map(|(k,v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect())).collect()
 }
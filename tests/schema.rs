@@ -0,0 +1,58 @@
+use pretty_assertions::assert_eq;
+use argmap::{Kind,Schema,SchemaError};
+
+fn schema() -> Schema {
+  Schema::new()
+    .flag("verbose", Kind::Bool)
+    .flag("v", Kind::Bool)
+    .flag("port", Kind::U16)
+    .flag("input", Kind::Str)
+    .required("input")
+}
+
+#[test] fn schema_ok() {
+  let parsed = schema().parse(["-v","--port=80","--input","a.txt","b"].iter()).unwrap();
+  assert![parsed.flag("v")];
+  assert![!parsed.flag("verbose")];
+  assert_eq![parsed.get::<u16>("port"), Some(80)];
+  assert_eq![parsed.get_str("input"), Some("a.txt")];
+  assert_eq![parsed.args(), &vec!["b".to_string()]];
+}
+
+#[test] fn schema_collects_errors() {
+  let res = schema().parse(["--port","99999","--verbose=yes","-x","--port"].iter());
+  assert_eq![res, Err(vec![
+    SchemaError::InvalidValue {
+      key: "port".to_string(),
+      value: "99999".to_string(),
+      kind: Kind::U16,
+    },
+    SchemaError::InvalidValue {
+      key: "verbose".to_string(),
+      value: "yes".to_string(),
+      kind: Kind::Bool,
+    },
+    SchemaError::UnknownKey { key: "x".to_string() },
+    SchemaError::MissingRequired { key: "input".to_string() },
+  ])];
+  let res = schema().parse(["--input"].iter());
+  assert_eq![res, Err(vec![SchemaError::MissingValue { key: "input".to_string() }])];
+}
+
+#[test] fn schema_required_registers_key() {
+  let schema = Schema::new()
+    .flag("verbose", Kind::Bool)
+    .flag("port", Kind::U16)
+    .required("input");
+  let parsed = schema.parse(["--input","x.txt"].iter()).unwrap();
+  assert_eq![parsed.get_str("input"), Some("x.txt")];
+  assert_eq![schema.parse(["--port","80"].iter()), Err(vec![
+    SchemaError::MissingRequired { key: "input".to_string() },
+  ])];
+  let schema = Schema::new().required("port").flag("port", Kind::U16);
+  assert_eq![schema.parse(["--port","x"].iter()), Err(vec![SchemaError::InvalidValue {
+    key: "port".to_string(),
+    value: "x".to_string(),
+    kind: Kind::U16,
+  }])];
+}
@@ -0,0 +1,178 @@
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+use argmap::ParseError;
+
+#[test] fn strict_double_equals() {
+  let res = argmap::new().strict(true).try_parse(["--key==x"].iter());
+  assert_eq![res, Err(ParseError::SuspiciousValue {
+    key: "key".to_string(),
+    value: "=x".to_string(),
+  })];
+  let res = argmap::new().strict(true).try_parse(["-k==x"].iter());
+  assert_eq![res, Err(ParseError::SuspiciousValue {
+    key: "k".to_string(),
+    value: "=x".to_string(),
+  })];
+}
+
+#[test] fn strict_empty_value() {
+  let res = argmap::new().strict(true).try_parse(["a","--key=","b"].iter());
+  assert_eq![res, Err(ParseError::SuspiciousValue {
+    key: "key".to_string(),
+    value: "".to_string(),
+  })];
+}
+
+#[test] fn strict_ok_values() {
+  let res = argmap::new().strict(true).try_parse(["--key=a=b","--k","=x"].iter());
+  assert_eq![res, Ok((vec![],hash([
+    ("key",vec!["a=b"]),
+    ("k",vec!["=x"]),
+  ].iter())))];
+}
+
+#[test] fn lenient_suspicious_values() {
+  let input = ["--key==x","--empty="];
+  let expected = hash([
+    ("key",vec!["=x"]),
+    ("empty",vec![""]),
+  ].iter());
+  assert_eq![argmap::new().try_parse(input.iter()), Ok((vec![],expected.clone()))];
+  assert_eq![argmap::new().strict(true).parse(input.iter()), (vec![],expected)];
+}
+
+#[test] fn max_cluster_len() {
+  let long = format!["-{}", "x".repeat(100)];
+  let parser = argmap::new().max_cluster_len(10);
+  let (args,argv) = parser.parse([long.as_str(), "value", "-abc"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([
+    (&long[1..],vec!["value"]),
+    ("a",vec![]),
+    ("b",vec![]),
+    ("c",vec![]),
+  ].iter())];
+  let res = argmap::new().max_cluster_len(10).strict(true).try_parse([long.as_str()].iter());
+  assert_eq![res, Err(ParseError::ClusterTooLong { token: long.clone() })];
+  let res = argmap::new().max_cluster_len(3).strict(true).try_parse(["-abc"].iter());
+  assert![res.is_ok()];
+}
+
+#[test] fn likely_long_flag() {
+  let parser = argmap::new().boolean("verbose").known(&["output"]).strict(true);
+  assert_eq![parser.try_parse(["-verbose"].iter()), Err(ParseError::LikelyLongFlag {
+    token: "-verbose".to_string(),
+  })];
+  assert_eq![parser.try_parse(["-output=x"].iter()), Err(ParseError::LikelyLongFlag {
+    token: "-output=x".to_string(),
+  })];
+  assert_eq![parser.try_parse(["-vx","--verbose"].iter()), Ok((vec![],hash([
+    ("v",vec![]),
+    ("x",vec![]),
+    ("verbose",vec![]),
+  ].iter())))];
+  let (_args,argv) = parser.parse(["-verbose"].iter());
+  assert_eq![argv.len(), 6];
+}
+
+#[test] fn strict_missing_value() {
+  let parser = argmap::new().value("output").strict(true);
+  let res = parser.try_parse(["--output","--verbose"].iter());
+  assert_eq![res, Err(ParseError::MissingValue { key: "output".to_string() })];
+  let res = parser.try_parse(["--verbose","--output"].iter());
+  assert_eq![res, Err(ParseError::MissingValue { key: "output".to_string() })];
+  let res = parser.try_parse(["--output","-5","--other","--verbose"].iter());
+  assert_eq![res, Ok((vec![],hash([
+    ("output",vec!["-5"]),
+    ("other",vec![]),
+    ("verbose",vec![]),
+  ].iter())))];
+  let res = argmap::new().strict(true).try_parse(["--output","--verbose"].iter());
+  assert_eq![res, Ok((vec![],hash([("output",vec![]),("verbose",vec![])].iter())))];
+  let (args,argv) = parser.parse(["--output","--verbose"].iter());
+  assert_eq![args, Vec::<String>::new()];
+  assert_eq![argv, hash([("output",vec![]),("verbose",vec![])].iter())];
+}
+
+#[test] fn strict_unknown_cluster() {
+  let parser = argmap::new().known(&["x","v","f"]).strict(true);
+  let res = parser.try_parse(["-xvz"].iter());
+  assert_eq![res, Err(ParseError::UnknownCluster { token: "-xvz".to_string(), bad_char: 'z' })];
+  let res = parser.try_parse(["-xqv","file"].iter());
+  assert_eq![res, Err(ParseError::UnknownCluster { token: "-xqv".to_string(), bad_char: 'q' })];
+  let res = parser.try_parse(["-xvf5","-z"].iter());
+  assert_eq![res, Ok((vec![],hash([
+    ("x",vec![]),
+    ("v",vec![]),
+    ("f",vec!["5"]),
+    ("z",vec![]),
+  ].iter())))];
+  let (_args,argv) = parser.parse(["-xvz"].iter());
+  assert_eq![argv, hash([("x",vec![]),("v",vec![]),("z",vec![])].iter())];
+}
+
+#[test] fn max_value_len() {
+  let long = "x".repeat(100);
+  let input = ["--key", long.as_str(), "-k=abé", "--ok=abc"];
+  let res = argmap::new().max_value_len(3).strict(true).try_parse(input.iter());
+  assert_eq![res, Err(ParseError::ValueTooLong { key: "key".to_string(), len: 100 })];
+  let res = argmap::new().max_value_len(3).try_parse(input.iter());
+  assert_eq![res, Ok((vec![],hash([
+    ("key",vec!["xxx"]),
+    ("k",vec!["ab"]),
+    ("ok",vec!["abc"]),
+  ].iter())))];
+  let res = argmap::new().max_value_len(100).strict(true).try_parse(input[..2].iter());
+  assert_eq![res, Ok((vec![],hash([("key",vec![long.as_str()])].iter())))];
+  let endless = input[..2].iter().chain(std::iter::repeat(&"pos"));
+  let res = argmap::new().max_value_len(3).strict(true).try_parse(endless);
+  assert_eq![res, Err(ParseError::ValueTooLong { key: "key".to_string(), len: 100 })];
+}
+
+#[test] fn strict_flags_before_positionals() {
+  let parser = argmap::new().booleans(&["v","q"]).flags_before_positionals(true).strict(true);
+  let res = parser.try_parse(["-v", "run", "-q", "-x"].iter());
+  assert_eq![res, Err(ParseError::FlagAfterPositional { key: "q".to_string() })];
+  let res = parser.try_parse(["-v", "run", "-x", "--", "-q"].iter());
+  assert_eq![res, Ok((vec!["run".to_string(),"-q".to_string()],hash([
+    ("v",vec![]),
+    ("x",vec![]),
+  ].iter())))];
+  let (args,argv) = parser.parse(["run", "-q"].iter());
+  assert_eq![args, vec!["run"]];
+  assert_eq![argv, hash([("q",vec![])].iter())];
+}
+
+#[test] fn strict_repeatable() {
+  let parser = argmap::new().repeatable(&["include"]).counted("v").strict(true);
+  let res = parser.try_parse(["--include", "a", "-vv", "--include=b", "--out", "x"].iter());
+  assert_eq![res, Ok((vec![],hash([
+    ("include",vec!["a","b"]),
+    ("v",vec!["2"]),
+    ("out",vec!["x"]),
+  ].iter())))];
+  let res = parser.try_parse(["--out", "x", "--include", "a", "--out", "y"].iter());
+  assert_eq![res, Err(ParseError::Repeated { key: "out".to_string() })];
+  let (_args,argv) = parser.parse(["--out", "x", "--out", "y"].iter());
+  assert_eq![argv, hash([("out",vec!["x","y"])].iter())];
+  let res = argmap::new().strict(true).try_parse(["--out", "x", "--out", "y"].iter());
+  assert![res.is_ok()];
+}
+
+#[test] fn strict_value_pattern() {
+  let no_space = || Box::new(|v: &str| !v.contains(char::is_whitespace));
+  let parser = argmap::new().value_pattern(no_space()).strict(true);
+  let res = parser.try_parse(["--name=bob", "-x", "a b", "pos with space"].iter());
+  assert_eq![res, Err(ParseError::PatternMismatch {
+    key: "x".to_string(),
+    value: "a b".to_string(),
+  })];
+  let res = parser.try_parse(["--name=bob", "pos with space"].iter());
+  assert_eq![res, Ok((vec!["pos with space".to_string()],hash([("name",vec!["bob"])].iter())))];
+  let (_args,argv) = argmap::new().value_pattern(no_space()).parse(["-x", "a b"].iter());
+  assert_eq![argv, hash([("x",vec!["a b"])].iter())];
+}
+
+fn hash<'a>(i: impl Iterator<Item=&'a (&'a str,Vec<&'a str>)>) -> HashMap<String,Vec<String>> {
+  i.map(|(k,v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect())).collect()
+}
@@ -0,0 +1,39 @@
+use pretty_assertions::assert_eq;
+use argmap::TokenKind;
+
+#[test] fn token_kind_without_pending() {
+  let parser = argmap::new();
+  assert_eq![parser.token_kind("--", None), TokenKind::Separator];
+  assert_eq![parser.token_kind("--file", None), TokenKind::Long];
+  assert_eq![parser.token_kind("--file=x", None), TokenKind::Long];
+  assert_eq![parser.token_kind("-x", None), TokenKind::Short];
+  assert_eq![parser.token_kind("-x=5", None), TokenKind::Short];
+  assert_eq![parser.token_kind("-xvf", None), TokenKind::Cluster];
+  assert_eq![parser.token_kind("-n5", None), TokenKind::Cluster];
+  assert_eq![parser.token_kind("-5", None), TokenKind::Short];
+  assert_eq![parser.token_kind("file", None), TokenKind::Positional];
+  assert_eq![parser.token_kind("-", None), TokenKind::Positional];
+  assert_eq![parser.token_kind("--=x", None), TokenKind::Positional];
+}
+
+#[test] fn token_kind_with_pending() {
+  let parser = argmap::new();
+  assert_eq![parser.token_kind("file", Some("x")), TokenKind::Value];
+  assert_eq![parser.token_kind("-", Some("x")), TokenKind::Value];
+  assert_eq![parser.token_kind("-5", Some("x")), TokenKind::Value];
+  assert_eq![parser.token_kind("--=x", Some("x")), TokenKind::Value];
+  assert_eq![parser.token_kind("--", Some("x")), TokenKind::Separator];
+  assert_eq![parser.token_kind("--file", Some("x")), TokenKind::Long];
+  assert_eq![parser.token_kind("-v", Some("x")), TokenKind::Short];
+  assert_eq![parser.token_kind("-xv", Some("x")), TokenKind::Cluster];
+}
+
+#[test] fn token_kind_settings() {
+  let parser = argmap::new().no_clustering(true).backslash_escape(true);
+  assert_eq![parser.token_kind("-xvf", None), TokenKind::Long];
+  assert_eq![parser.token_kind(r"\-x", None), TokenKind::Positional];
+  assert_eq![parser.token_kind(r"\-x", Some("f")), TokenKind::Value];
+  let parser = argmap::new().max_cluster_len(2).autocorrect_dashes(true);
+  assert_eq![parser.token_kind("-xvf", None), TokenKind::Long];
+  assert_eq![parser.token_kind("\u{2014}file", Some("f")), TokenKind::Long];
+}
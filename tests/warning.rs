@@ -0,0 +1,98 @@
+use pretty_assertions::assert_eq;
+use argmap::{ArgMap,Warning};
+use std::{rc::Rc,cell::RefCell};
+
+#[test] fn warning_repeated_separator() {
+  let (args,warnings) = parse(argmap::new(), &["a", "--", "--", "b", "--"]);
+  assert_eq![args, vec!["a","--","b","--"]];
+  assert_eq![warnings, vec![
+    Warning::RepeatedSeparator { index: 2 },
+    Warning::RepeatedSeparator { index: 4 },
+  ]];
+  let (args,warnings) = parse(argmap::new().raw_after("run"), &["run", "--", "x"]);
+  assert_eq![args, vec!["run","--","x"]];
+  assert_eq![warnings, vec![]];
+}
+
+#[test] fn warning_value_on_boolean() {
+  let input = ["--verbose=yes", "-v=1", "--other=x"];
+  let (_args,warnings) = parse(argmap::new().booleans(&["verbose","v"]), &input);
+  assert_eq![warnings, vec![]];
+  let (_args,warnings) = parse(argmap::new().booleans(&["verbose","v"]).strict(true), &input);
+  assert_eq![warnings, vec![]];
+  let (_args,warnings) = try_parse(argmap::new().booleans(&["verbose","v"]).strict(true), &input);
+  assert_eq![warnings, vec![
+    Warning::ValueOnBoolean { key: "verbose".to_string(), value: "yes".to_string() },
+    Warning::ValueOnBoolean { key: "v".to_string(), value: "1".to_string() },
+  ]];
+  let (_args,argv) = argmap::new().boolean("verbose").strict(true).parse(input.iter());
+  assert_eq![argv.get("verbose"), Some(&vec!["yes".to_string()])];
+}
+
+#[test] fn warning_possible_missing_value() {
+  let parser = argmap::new().value("name").boolean("verbose").dash_values(true);
+  let (args,warnings) = parse(parser, &["--name", "--verbose", "--name", "-x", "pos"]);
+  assert_eq![args, vec!["pos"]];
+  assert_eq![warnings, vec![
+    Warning::PossibleMissingValue { key: "name".to_string(), consumed: "--verbose".to_string() },
+  ]];
+  let parser = argmap::new().value("name").boolean("verbose").dash_values(true);
+  let (_args,argv) = parser.parse(["--name", "--verbose", "-v"].iter());
+  assert_eq![argv.get("name"), Some(&vec!["--verbose".to_string()])];
+  assert_eq![argv.get("verbose"), None];
+  let (_args,warnings) = parse(argmap::new().value("name").boolean("verbose"), &[
+    "--name", "--verbose",
+  ]);
+  assert_eq![warnings, vec![]];
+}
+
+#[test] fn warning_flag_after_positional() {
+  let parser = argmap::new().booleans(&["v","q"]).flags_before_positionals(true);
+  let (args,warnings) = parse(parser, &["-v", "run", "-q", "-x", "-vq"]);
+  assert_eq![args, vec!["run"]];
+  assert_eq![warnings, vec![
+    Warning::FlagAfterPositional { key: "q".to_string(), index: 2 },
+    Warning::FlagAfterPositional { key: "v".to_string(), index: 4 },
+    Warning::FlagAfterPositional { key: "q".to_string(), index: 4 },
+  ]];
+}
+
+#[test] fn warning_contradiction() {
+  let parser = || argmap::new().negatable("color").negatable("pager").alias("c","color");
+  let (_args,warnings) = parse(parser(), &["--color", "--no-pager", "--no-color"]);
+  assert_eq![warnings, vec![Warning::Contradiction { key: "color".to_string() }]];
+  let (_args,argv) = parser().parse(["--color", "--no-pager", "--no-color"].iter());
+  assert![!argv.contains_key("color")];
+  let (_args,warnings) = parse(parser(), &["--no-color", "-c", "--pager", "--no-pager"]);
+  assert_eq![warnings, vec![
+    Warning::Contradiction { key: "color".to_string() },
+    Warning::Contradiction { key: "pager".to_string() },
+  ]];
+  let (_args,argv) = parser().parse(["--no-color", "-c"].iter());
+  assert![argv.contains_key("color")];
+  let (_args,warnings) = parse(parser(), &["--color", "--color", "--no-pager"]);
+  assert_eq![warnings, vec![]];
+  let parser = || argmap::new().counted("v").negatable("v");
+  let (_args,warnings) = parse(parser(), &["-vvv", "--no-v"]);
+  assert_eq![warnings, vec![]];
+  let (_args,argv) = parser().parse(["-vvv", "--no-v"].iter());
+  assert_eq![argv.get("v"), Some(&vec!["2".to_string()])];
+}
+
+fn parse(parser: ArgMap, input: &[&str]) -> (Vec<String>,Vec<Warning>) {
+  let warnings = Rc::new(RefCell::new(vec![]));
+  let w = warnings.clone();
+  let parser = parser.on_warning(Box::new(move |warning| w.borrow_mut().push(warning.clone())));
+  let (args,_argv) = parser.parse(input.iter());
+  let warnings = warnings.borrow().clone();
+  (args,warnings)
+}
+
+fn try_parse(parser: ArgMap, input: &[&str]) -> (Vec<String>,Vec<Warning>) {
+  let warnings = Rc::new(RefCell::new(vec![]));
+  let w = warnings.clone();
+  let parser = parser.on_warning(Box::new(move |warning| w.borrow_mut().push(warning.clone())));
+  let (args,_argv) = parser.try_parse(input.iter()).unwrap();
+  let warnings = warnings.borrow().clone();
+  (args,warnings)
+}